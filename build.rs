@@ -8,9 +8,16 @@ use std::path::Path;
 
 use std::process;
 
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// Sentinel returned by `get_git_commit_hash` (and threaded through from there) when there's no
+/// `.git` directory to inspect.
+const NOT_BUILT_FROM_GIT: &str = "[not built from git repo]";
+
 fn get_git_commit_hash() -> String {
     if !Path::new(".git").is_dir() {
-        return "[not built from git repo]".to_string();
+        return NOT_BUILT_FROM_GIT.to_string();
     }
 
     let git_result = process::Command::new("git")
@@ -38,8 +45,80 @@ fn get_git_commit_hash() -> String {
     git_stdout.trim().to_string()
 }
 
+/// Shortens `full_hash` to the conventional 7-character abbreviation, leaving the
+/// `NOT_BUILT_FROM_GIT` sentinel (or any other short string) untouched.
+fn get_git_commit_short(full_hash: &str) -> String {
+    if full_hash == NOT_BUILT_FROM_GIT || full_hash.len() < 7 {
+        full_hash.to_string()
+    } else {
+        full_hash[..7].to_string()
+    }
+}
+
+/// Whether the working tree had uncommitted changes at build time. `false` when there's no `.git`
+/// directory, same as a from-source tarball with nothing to be dirty relative to.
+fn is_git_tree_dirty() -> bool {
+    if !Path::new(".git").is_dir() {
+        return false;
+    }
+
+    let git_result = process::Command::new("git")
+        .arg("status")
+        .arg("--porcelain")
+        .output();
+
+    let git_output = match git_result {
+        Ok(o) => o,
+        Err(_) => panic!("could not run git-status"),
+    };
+
+    if !git_output.status.success() {
+        panic!("git-status failed. exit code: {:?}", git_output.status.code());
+    }
+
+    !git_output.stdout.is_empty()
+}
+
+/// Converts a day count since the Unix epoch (1970-01-01) into a `(year, month, day)` civil date.
+/// Avoids pulling in a date/time crate just for this; algorithm is Howard Hinnant's
+/// `civil_from_days` (http://howardhinnant.github.io/date_algorithms.html).
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = (z - era * 146097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = if m <= 2 { y + 1 } else { y };
+
+    (year, m, d)
+}
+
+/// Returns the current UTC time formatted as an ISO-8601 timestamp, e.g. `2024-01-02T03:04:05Z`.
+fn get_build_timestamp() -> String {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is set before 1970");
+
+    let secs = since_epoch.as_secs();
+    let (days, time_of_day) = (secs / 86400, secs % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+    let (hour, minute, second) = (time_of_day / 3600, (time_of_day % 3600) / 60, time_of_day % 60);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, minute, second
+    )
+}
+
 fn main() {
     let git_commit = get_git_commit_hash();
+    let git_commit_short = get_git_commit_short(&git_commit);
+    let git_dirty = is_git_tree_dirty();
+    let build_timestamp = get_build_timestamp();
 
     let host_triple = env::var("HOST").unwrap();
     let target_triple = env::var("TARGET").unwrap();
@@ -59,8 +138,23 @@ fn main() {
             println!(\"target triple: {}\");
             println!(\"profile: {}\");
         }}
+
+        /// Short (7-character) git commit hash this build was compiled from.
+        pub const GIT_COMMIT_SHORT: &str = \"{}\";
+
+        /// Whether the working tree had uncommitted changes at build time.
+        pub const GIT_DIRTY: bool = {};
+
+        /// UTC timestamp this build was compiled at, e.g. `2024-01-02T03:04:05Z`.
+        pub const BUILD_TIMESTAMP: &str = \"{}\";
     ",
-        git_commit, host_triple, target_triple, profile
+        git_commit,
+        host_triple,
+        target_triple,
+        profile,
+        git_commit_short,
+        git_dirty,
+        build_timestamp,
     )
     .unwrap();
 
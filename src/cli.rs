@@ -21,6 +21,20 @@ pub enum TtyEnabledOutput {
     Auto,
 }
 
+/// Selects how diff records are serialized on stdout.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+    /// one-character indicator lines (the original `durduff` format)
+    Text,
+
+    /// a single JSON document: `{"records":[...],"summary":{...}}`
+    Json,
+
+    /// one JSON object per record, one record per line, so the stream stays consumable
+    /// incrementally (NDJSON)
+    Ndjson,
+}
+
 /// Result of successfully parsing CLI args
 #[derive(Debug, Eq, PartialEq)]
 pub struct CliArgs {
@@ -30,8 +44,52 @@ pub struct CliArgs {
     pub color: TtyEnabledOutput,
     pub progress: TtyEnabledOutput,
 
+    /// Raw `role=spec:role=spec...` palette override (see `io::Palette::apply`), from `--palette`.
+    /// `DURDUFF_COLORS` is consulted instead whenever this is `None`.
+    pub palette: Option<String>,
+
+    pub format: OutputFormat,
+
     pub block_size: Option<usize>,
 
+    pub hash: bool,
+
+    pub config: Option<PathBuf>,
+
+    /// Gitignore/pathspec-style exclude patterns, applied (together with `include` and
+    /// `exclude_from`) against each entry's path relative to `old_dir`/`new_dir`.
+    pub exclude: Vec<String>,
+
+    /// Gitignore/pathspec-style patterns that re-include a path otherwise excluded by `exclude`
+    /// or `exclude_from`.
+    pub include: Vec<String>,
+
+    /// File of newline-separated `exclude`-style patterns (`#`-prefixed lines and blank lines
+    /// ignored), read as the first layer of the path filter.
+    pub exclude_from: Option<PathBuf>,
+
+    pub jobs: Option<usize>,
+
+    /// `-L`/`--dereference`-style traversal: descend into symlinked subdirectories instead of
+    /// reporting them as plain entries, guarding against symlink cycles.
+    pub dereference: bool,
+
+    pub detect_renames: bool,
+
+    /// When set, `<old>` is not read: `new_dir` is compared against the manifest at this path
+    /// instead of a second live directory.
+    pub old_manifest: Option<PathBuf>,
+
+    /// When set, no comparison is performed: a manifest of `old_dir` is written to this path and
+    /// the program exits.
+    pub generate_manifest: Option<PathBuf>,
+
+    /// Treat `old_dir` as a tar archive path instead of a directory, regardless of its extension.
+    pub old_tar: bool,
+
+    /// Treat `new_dir` as a tar archive path instead of a directory, regardless of its extension.
+    pub new_tar: bool,
+
     pub old_dir: PathBuf,
     pub new_dir: PathBuf,
 }
@@ -50,6 +108,44 @@ pub fn parse_cli(args: &[OsString]) -> Cli {
         }
     }
 
+    fn is_valid_jobs(s: String) -> Result<(), String> {
+        match s.parse::<usize>() {
+            Ok(0) | Err(_) => Err(s),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    /// `<old>`/`<new>` are plain positional args in clap 2.x, with a fixed `.index(n)` that can't
+    /// be chosen conditionally once the `App` is built. So, before building it, scan the raw args
+    /// for `--old-manifest`/`--generate-manifest` to decide up front how many positional
+    /// directories this invocation takes and what they mean.
+    fn has_flag(args: &[OsString], flag: &str) -> bool {
+        let prefix = format!("{}=", flag);
+
+        args.iter()
+            .filter_map(|a| a.to_str())
+            .any(|a| a == flag || a.starts_with(&prefix))
+    }
+
+    enum PositionalMode {
+        /// `<old> <new>`: compare two live directories.
+        TwoDirs,
+        /// `<new>` only: compare a live directory against `--old-manifest`.
+        OldManifest,
+        /// `<dir>` only: write a manifest of `<dir>` to `--generate-manifest` and exit.
+        GenerateManifest,
+    }
+
+    let rest_args = args.get(1..).unwrap_or(&[]);
+
+    let positional_mode = if has_flag(rest_args, "--generate-manifest") {
+        PositionalMode::GenerateManifest
+    } else if has_flag(rest_args, "--old-manifest") {
+        PositionalMode::OldManifest
+    } else {
+        PositionalMode::TwoDirs
+    };
+
     let version = get_version();
 
     let pkg_name = env!("CARGO_PKG_NAME");
@@ -81,6 +177,14 @@ pub fn parse_cli(args: &[OsString]) -> Cli {
             .possible_values(&["never", "always", "auto"])
             .default_value("auto")
             .display_order(3))
+        .arg(Arg::with_name("palette")
+            .long("palette")
+            .value_name("spec")
+            .help("Override diff colors: colon-separated role=spec list (role: added, removed, \
+                   changed, type_changed, renamed, error, context, progress; spec: a color name, \
+                   256;N, or rgb:RR/GG/BB), also readable from DURDUFF_COLORS")
+            .takes_value(true)
+            .display_order(4))
         .arg(Arg::with_name("progress")
             .long("progress")
             .value_name("when")
@@ -88,7 +192,7 @@ pub fn parse_cli(args: &[OsString]) -> Cli {
             .takes_value(true)
             .possible_values(&["never", "always", "auto"])
             .default_value("auto")
-            .display_order(4))
+            .display_order(5))
         .arg(Arg::with_name("block-size")
             .short("b")
             .long("block-size")
@@ -96,19 +200,116 @@ pub fn parse_cli(args: &[OsString]) -> Cli {
             .help("Read files in blocks of <block-size> bytes")
             .takes_value(true)
             .validator(is_valid_block_size)
-            .display_order(5))
+            .display_order(6))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("format")
+            .help("Select the output format")
+            .takes_value(true)
+            .possible_values(&["text", "json", "ndjson"])
+            .default_value("text")
+            .display_order(7))
+        .arg(Arg::with_name("hash")
+            .long("hash")
+            .help("Compare file contents by hashing each side independently")
+            .display_order(8))
+        .arg(Arg::with_name("config")
+            .long("config")
+            .value_name("file")
+            .help("Read exclude patterns from a layered config file")
+            .takes_value(true)
+            .display_order(9))
+        .arg(Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("glob")
+            .help("Exclude paths matching <glob> (gitignore-style, repeatable)")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .display_order(10))
+        .arg(Arg::with_name("include")
+            .long("include")
+            .value_name("glob")
+            .help("Re-include paths matching <glob> that an --exclude/--exclude-from pattern excluded (repeatable)")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .display_order(11))
+        .arg(Arg::with_name("exclude-from")
+            .long("exclude-from")
+            .value_name("file")
+            .help("Read --exclude-style patterns, one per line, from <file>")
+            .takes_value(true)
+            .display_order(12))
+        .arg(Arg::with_name("jobs")
+            .short("j")
+            .long("jobs")
+            .value_name("jobs")
+            .help("Compare file contents across <jobs> worker threads (default: available parallelism)")
+            .takes_value(true)
+            .validator(is_valid_jobs)
+            .display_order(13))
+        .arg(Arg::with_name("dereference")
+            .short("L")
+            .long("dereference")
+            .help("Follow symlinked directories instead of reporting them as plain entries")
+            .display_order(14))
+        .arg(Arg::with_name("detect-renames")
+            .long("detect-renames")
+            .help("Recognize relocated files, reporting them as renames instead of an add+delete pair")
+            .conflicts_with("old-manifest")
+            .display_order(15))
+        .arg(Arg::with_name("old-manifest")
+            .long("old-manifest")
+            .value_name("file")
+            .help("Compare <new> against a manifest file instead of a second directory")
+            .takes_value(true)
+            .conflicts_with("generate-manifest")
+            .display_order(16))
+        .arg(Arg::with_name("generate-manifest")
+            .long("generate-manifest")
+            .value_name("file")
+            .help("Write a manifest of <old> to <file>, for a later --old-manifest comparison, instead of comparing two directories")
+            .takes_value(true)
+            .display_order(17))
+        .arg(Arg::with_name("old-tar")
+            .long("old-tar")
+            .help("Treat <old> as a tar archive instead of a directory (default: autodetected from a .tar extension)")
+            .conflicts_with_all(&["old-manifest", "generate-manifest"])
+            .display_order(18))
+        .arg(Arg::with_name("new-tar")
+            .long("new-tar")
+            .help("Treat <new> as a tar archive instead of a directory (default: autodetected from a .tar extension)")
+            .conflicts_with("generate-manifest")
+            .display_order(19))
         .help_message("Print help information and exit")
-        .version_message("Print version information and exit")
-        .arg(Arg::with_name("old")
-            .value_name("old")
-            .required_unless_one(&["help", "version"])
-            .hidden(true)
-            .index(1))
-        .arg(Arg::with_name("new")
-            .value_name("new")
-            .required_unless_one(&["help", "version"])
-            .hidden(true)
-            .index(2));
+        .version_message("Print version information and exit");
+
+    let app = match positional_mode {
+        PositionalMode::TwoDirs => app
+            .arg(Arg::with_name("old")
+                .value_name("old")
+                .required_unless_one(&["help", "version"])
+                .hidden(true)
+                .index(1))
+            .arg(Arg::with_name("new")
+                .value_name("new")
+                .required_unless_one(&["help", "version"])
+                .hidden(true)
+                .index(2)),
+        PositionalMode::OldManifest => app
+            .arg(Arg::with_name("new")
+                .value_name("new")
+                .required_unless_one(&["help", "version"])
+                .hidden(true)
+                .index(1)),
+        PositionalMode::GenerateManifest => app
+            .arg(Arg::with_name("old")
+                .value_name("dir")
+                .required_unless_one(&["help", "version"])
+                .hidden(true)
+                .index(1)),
+    };
 
     fn parse_after_bin_name(app: clap::App, args: &[OsString]) -> Result<CliArgs, clap::Error> {
         let matches = app.get_matches_from_safe(args)?;
@@ -125,6 +326,19 @@ pub fn parse_cli(args: &[OsString]) -> Cli {
         let color = parse_tty_enabled(matches.value_of_lossy("color").unwrap().as_ref());
         let progress = parse_tty_enabled(matches.value_of_lossy("progress").unwrap().as_ref());
 
+        let palette = matches.value_of_lossy("palette").map(|p| p.into_owned());
+
+        fn parse_format(s: &str) -> OutputFormat {
+            match s {
+                "text" => OutputFormat::Text,
+                "json" => OutputFormat::Json,
+                "ndjson" => OutputFormat::Ndjson,
+                &_ => unreachable!(),
+            }
+        }
+
+        let format = parse_format(matches.value_of_lossy("format").unwrap().as_ref());
+
         fn get_path(o: Option<&OsStr>) -> PathBuf {
             PathBuf::from(o.unwrap_or(OsStr::new("")).to_owned())
         }
@@ -133,6 +347,23 @@ pub fn parse_cli(args: &[OsString]) -> Cli {
             .value_of("block-size")
             .map(|b| b.parse::<usize>().unwrap());
 
+        let config = matches.value_of_os("config").map(PathBuf::from);
+
+        let exclude = matches
+            .values_of_lossy("exclude")
+            .unwrap_or_default();
+
+        let include = matches
+            .values_of_lossy("include")
+            .unwrap_or_default();
+
+        let exclude_from = matches.value_of_os("exclude-from").map(PathBuf::from);
+
+        let jobs = matches.value_of("jobs").map(|j| j.parse::<usize>().unwrap());
+
+        let old_manifest = matches.value_of_os("old-manifest").map(PathBuf::from);
+        let generate_manifest = matches.value_of_os("generate-manifest").map(PathBuf::from);
+
         Ok(CliArgs {
             brief: matches.is_present("brief"),
             nul_terminated: matches.is_present("null"),
@@ -140,8 +371,32 @@ pub fn parse_cli(args: &[OsString]) -> Cli {
             color,
             progress,
 
+            palette,
+
+            format,
+
             block_size,
 
+            hash: matches.is_present("hash"),
+
+            config,
+
+            exclude,
+            include,
+            exclude_from,
+
+            jobs,
+
+            dereference: matches.is_present("dereference"),
+
+            detect_renames: matches.is_present("detect-renames"),
+
+            old_manifest,
+            generate_manifest,
+
+            old_tar: matches.is_present("old-tar"),
+            new_tar: matches.is_present("new-tar"),
+
             old_dir: get_path(matches.value_of_os("old")),
             new_dir: get_path(matches.value_of_os("new")),
         })
@@ -367,7 +622,29 @@ mod tests {
             color: TtyEnabledOutput::Always,
             progress: TtyEnabledOutput::Never,
 
+            palette: None,
+
+            format: OutputFormat::Text,
+
             block_size: Some(400),
+            hash: false,
+            config: None,
+
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exclude_from: None,
+
+            jobs: None,
+
+            dereference: false,
+
+            detect_renames: false,
+
+            old_manifest: None,
+            generate_manifest: None,
+
+            old_tar: false,
+            new_tar: false,
 
             old_dir: PathBuf::from("happy"),
             new_dir: PathBuf::from("panda"),
@@ -385,7 +662,29 @@ mod tests {
             color: TtyEnabledOutput::Never,
             progress: TtyEnabledOutput::Auto,
 
+            palette: None,
+
+            format: OutputFormat::Text,
+
             block_size: Some(600),
+            hash: false,
+            config: None,
+
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exclude_from: None,
+
+            jobs: None,
+
+            dereference: false,
+
+            detect_renames: false,
+
+            old_manifest: None,
+            generate_manifest: None,
+
+            old_tar: false,
+            new_tar: false,
 
             old_dir: PathBuf::from("inverted"),
             new_dir: PathBuf::from("panda"),
@@ -403,7 +702,29 @@ mod tests {
             color: TtyEnabledOutput::Auto,
             progress: TtyEnabledOutput::Never,
 
+            palette: None,
+
+            format: OutputFormat::Text,
+
             block_size: Some(1),
+            hash: false,
+            config: None,
+
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exclude_from: None,
+
+            jobs: None,
+
+            dereference: false,
+
+            detect_renames: false,
+
+            old_manifest: None,
+            generate_manifest: None,
+
+            old_tar: false,
+            new_tar: false,
 
             old_dir: PathBuf::from("/foo/bar.txt"),
             new_dir: PathBuf::from("baz"),
@@ -421,7 +742,29 @@ mod tests {
             color: TtyEnabledOutput::Always,
             progress: TtyEnabledOutput::Auto,
 
+            palette: None,
+
+            format: OutputFormat::Text,
+
             block_size: None,
+            hash: false,
+            config: None,
+
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exclude_from: None,
+
+            jobs: None,
+
+            dereference: false,
+
+            detect_renames: false,
+
+            old_manifest: None,
+            generate_manifest: None,
+
+            old_tar: false,
+            new_tar: false,
 
             old_dir: PathBuf::from("c"),
             new_dir: PathBuf::from(OsString::from_vec(NON_UTF8_BYTE_SEQ.to_vec())),
@@ -439,7 +782,29 @@ mod tests {
             color: TtyEnabledOutput::Never,
             progress: TtyEnabledOutput::Always,
 
+            palette: None,
+
+            format: OutputFormat::Text,
+
             block_size: Some(512 << 10),
+            hash: false,
+            config: None,
+
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exclude_from: None,
+
+            jobs: None,
+
+            dereference: false,
+
+            detect_renames: false,
+
+            old_manifest: None,
+            generate_manifest: None,
+
+            old_tar: false,
+            new_tar: false,
 
             old_dir: PathBuf::from("foo/bar.txt"),
             new_dir: PathBuf::from("foo/bar"),
@@ -457,7 +822,29 @@ mod tests {
             color: TtyEnabledOutput::Never,
             progress: TtyEnabledOutput::Always,
 
+            palette: None,
+
+            format: OutputFormat::Text,
+
             block_size: Some(usize::MAX),
+            hash: false,
+            config: None,
+
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exclude_from: None,
+
+            jobs: None,
+
+            dereference: false,
+
+            detect_renames: false,
+
+            old_manifest: None,
+            generate_manifest: None,
+
+            old_tar: false,
+            new_tar: false,
 
             old_dir: PathBuf::from("a/b"),
             new_dir: PathBuf::from("c"),
@@ -475,7 +862,29 @@ mod tests {
             color: TtyEnabledOutput::Never,
             progress: TtyEnabledOutput::Always,
 
+            palette: None,
+
+            format: OutputFormat::Text,
+
             block_size: Some(usize::MAX),
+            hash: false,
+            config: None,
+
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exclude_from: None,
+
+            jobs: None,
+
+            dereference: false,
+
+            detect_renames: false,
+
+            old_manifest: None,
+            generate_manifest: None,
+
+            old_tar: false,
+            new_tar: false,
 
             old_dir: PathBuf::from("a/b"),
             new_dir: PathBuf::from(""),
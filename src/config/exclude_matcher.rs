@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use regex::Regex;
+
+use super::Config;
+
+/// Translates a simple shell-style glob (`*` matches anything, `?` matches one char, everything
+/// else is literal) into an anchored `Regex`.
+fn glob_to_regex(glob: &str) -> Regex {
+    let mut pattern = String::from("^");
+
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            '.' | '+' | '(' | ')' | '|' | '^' | '$' | '[' | ']' | '{' | '}' | '\\' => {
+                pattern.push('\\');
+                pattern.push(c);
+            }
+            c => pattern.push(c),
+        }
+    }
+
+    pattern.push('$');
+
+    Regex::new(&pattern).unwrap()
+}
+
+/// Matches paths against the glob patterns listed in a config's `[exclude]` section.
+#[derive(Clone)]
+pub struct ExcludeMatcher {
+    patterns: Vec<Regex>,
+}
+
+impl ExcludeMatcher {
+    /// Matcher that excludes nothing.
+    pub fn empty() -> Self {
+        Self {
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Builds a matcher from the patterns listed as keys under `config`'s `[exclude]` section.
+    pub fn from_config(config: &Config) -> Self {
+        let patterns = match config.section("exclude") {
+            Some(section) => section.items.iter().map(|i| glob_to_regex(&i.key)).collect(),
+            None => Vec::new(),
+        };
+
+        Self { patterns }
+    }
+
+    /// Returns whether `path` matches any of the exclude patterns.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let path_str = path.to_string_lossy();
+
+        self.patterns.iter().any(|re| re.is_match(&path_str))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use super::super::ConfigItem;
+    use super::super::ConfigSection;
+
+    #[test]
+    fn matches_glob_patterns() {
+        let config = Config {
+            sections: vec![ConfigSection {
+                name: "exclude".to_string(),
+                items: vec![
+                    ConfigItem {
+                        key: "*.o".to_string(),
+                        value: String::new(),
+                    },
+                    ConfigItem {
+                        key: "target/debug".to_string(),
+                        value: String::new(),
+                    },
+                ],
+            }],
+        };
+
+        let matcher = ExcludeMatcher::from_config(&config);
+
+        assert!(matcher.is_excluded(&PathBuf::from("foo.o")));
+        assert!(matcher.is_excluded(&PathBuf::from("target/debug")));
+        assert!(!matcher.is_excluded(&PathBuf::from("foo.rs")));
+    }
+
+    #[test]
+    fn empty_matcher_excludes_nothing() {
+        let matcher = ExcludeMatcher::empty();
+
+        assert!(!matcher.is_excluded(&PathBuf::from("anything")));
+    }
+}
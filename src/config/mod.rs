@@ -0,0 +1,5 @@
+mod exclude_matcher;
+mod parser;
+
+pub use self::exclude_matcher::*;
+pub use self::parser::*;
@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+
+use std::fs;
+
+use std::io;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use regex::Regex;
+
+/// A single `key = value` item inside a `[section]` of a config file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigItem {
+    pub key: String,
+    pub value: String,
+}
+
+/// A named group of items, corresponding to one `[section]` header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigSection {
+    pub name: String,
+    pub items: Vec<ConfigItem>,
+}
+
+/// A config file parsed into ordered sections, with `%include` and `%unset` directives already
+/// resolved.
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct Config {
+    pub sections: Vec<ConfigSection>,
+}
+
+impl Config {
+    fn section_mut(&mut self, name: &str) -> &mut ConfigSection {
+        if let Some(i) = self.sections.iter().position(|s| s.name == name) {
+            &mut self.sections[i]
+        } else {
+            self.sections.push(ConfigSection {
+                name: name.to_string(),
+                items: Vec::new(),
+            });
+            self.sections.last_mut().unwrap()
+        }
+    }
+
+    fn set(&mut self, section: &str, key: &str, value: &str) {
+        self.section_mut(section).items.push(ConfigItem {
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    fn unset(&mut self, section: &str, key: &str) {
+        self.section_mut(section).items.retain(|i| i.key != key);
+    }
+
+    fn merge(&mut self, other: Config) {
+        for section in other.sections {
+            self.section_mut(&section.name).items.extend(section.items);
+        }
+    }
+
+    /// Returns the section named `name`, if one was present anywhere in the merged config.
+    pub fn section(&self, name: &str) -> Option<&ConfigSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+}
+
+/// Parses `path` (and, recursively, anything it `%include`s) into a merged `Config`.
+///
+/// `visited` guards against include cycles: it tracks the canonicalized paths already being
+/// parsed along the current include chain, and is restored on the way back out.
+pub fn parse_config_file(path: &Path, visited: &mut HashSet<PathBuf>) -> io::Result<Config> {
+    let canonical = path.canonicalize()?;
+
+    if !visited.insert(canonical.clone()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("include cycle detected at {}", path.display()),
+        ));
+    }
+
+    let contents = fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let result = parse_config_str(&contents, base_dir, visited);
+
+    visited.remove(&canonical);
+
+    result
+}
+
+/// Parses the body of a single config file, recursing into `%include`d files through
+/// `parse_config_file`.
+fn parse_config_str(
+    contents: &str,
+    base_dir: &Path,
+    visited: &mut HashSet<PathBuf>,
+) -> io::Result<Config> {
+    let section_re = Regex::new(r"^\[([^\[]+)\]\s*$").unwrap();
+    // The `=value` part is optional so a bare gitignore-style pattern line (no `=` at all, as
+    // found in an `[exclude]` section) becomes an item whose key is the whole line and whose
+    // value is empty, rather than being silently skipped.
+    let item_re = Regex::new(r"^([^=\s][^=]*?)(?:\s*=\s*((.*\S)?))?\s*$").unwrap();
+    let continuation_re = Regex::new(r"^\s+(\S|\S.*\S)\s*$").unwrap();
+
+    let mut config = Config::default();
+
+    let mut cur_section = String::new();
+    let mut last_key: Option<String> = None;
+
+    for line in contents.lines() {
+        let trimmed = line.trim_start();
+
+        if line.trim().is_empty() || trimmed.starts_with(';') || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("%include ") {
+            let included_path = base_dir.join(rest.trim());
+            let included = parse_config_file(&included_path, visited)?;
+            config.merge(included);
+            last_key = None;
+        } else if let Some(rest) = trimmed.strip_prefix("%unset ") {
+            config.unset(&cur_section, rest.trim());
+            last_key = None;
+        } else if let Some(caps) = section_re.captures(line) {
+            cur_section = caps[1].to_string();
+            last_key = None;
+        } else if let (Some(key), Some(caps)) = (&last_key, continuation_re.captures(line)) {
+            let section = config.section_mut(&cur_section);
+            if let Some(item) = section.items.iter_mut().rev().find(|i| &i.key == key) {
+                item.value.push('\n');
+                item.value.push_str(&caps[1]);
+            }
+        } else if let Some(caps) = item_re.captures(line) {
+            let key = caps[1].to_string();
+            let value = caps.get(2).map_or("", |m| m.as_str()).to_string();
+            config.set(&cur_section, &key, &value);
+            last_key = Some(key);
+        }
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_and_items() {
+        let config = parse_config_str(
+            "[exclude]\n\
+             target/\n\
+             *.o =\n\
+             \n\
+             ; a comment\n\
+             [other]\n\
+             key = value\n",
+            Path::new("."),
+            &mut HashSet::new(),
+        )
+        .unwrap();
+
+        let exclude = config.section("exclude").unwrap();
+        assert_eq!(exclude.items[0].key, "target/");
+        assert_eq!(exclude.items[1].key, "*.o");
+
+        let other = config.section("other").unwrap();
+        assert_eq!(other.items[0].key, "key");
+        assert_eq!(other.items[0].value, "value");
+    }
+
+    #[test]
+    fn continuation_appends_to_previous_value() {
+        let config = parse_config_str(
+            "[section]\n\
+             key = first\n\
+             \tsecond\n",
+            Path::new("."),
+            &mut HashSet::new(),
+        )
+        .unwrap();
+
+        let section = config.section("section").unwrap();
+        assert_eq!(section.items[0].value, "first\nsecond");
+    }
+
+    #[test]
+    fn unset_removes_earlier_key() {
+        let config = parse_config_str(
+            "[section]\n\
+             key = value\n\
+             %unset key\n",
+            Path::new("."),
+            &mut HashSet::new(),
+        )
+        .unwrap();
+
+        assert!(config.section("section").unwrap().items.is_empty());
+    }
+
+    /// Creates a fresh directory under the system temp dir for a single test, so `%include` can
+    /// be exercised against real files.
+    fn make_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("durduff-config-parser-test-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn include_pulls_in_other_files_sections() {
+        let dir = make_test_dir("include");
+
+        fs::write(dir.join("included.conf"), "[exclude]\ntarget/\n").unwrap();
+        fs::write(
+            dir.join("main.conf"),
+            "[other]\nkey = value\n%include included.conf\n",
+        )
+        .unwrap();
+
+        let config = parse_config_file(&dir.join("main.conf"), &mut HashSet::new()).unwrap();
+
+        let exclude = config.section("exclude").unwrap();
+        assert_eq!(exclude.items[0].key, "target/");
+
+        let other = config.section("other").unwrap();
+        assert_eq!(other.items[0].value, "value");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn include_cycle_is_rejected() {
+        let dir = make_test_dir("include-cycle");
+
+        fs::write(dir.join("a.conf"), "%include b.conf\n").unwrap();
+        fs::write(dir.join("b.conf"), "%include a.conf\n").unwrap();
+
+        let result = parse_config_file(&dir.join("a.conf"), &mut HashSet::new());
+
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn include_of_self_is_rejected() {
+        let dir = make_test_dir("include-self");
+
+        fs::write(dir.join("a.conf"), "%include a.conf\n").unwrap();
+
+        let result = parse_config_file(&dir.join("a.conf"), &mut HashSet::new());
+
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
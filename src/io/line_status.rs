@@ -8,6 +8,8 @@ pub enum LineStatus {
     Deleted,
     Added,
     Modified,
+    TypeChanged,
+    Renamed,
     Error,
     ErrorDescription,
 }
@@ -18,6 +20,8 @@ impl LineStatus {
             LineStatus::Deleted => '-',
             LineStatus::Added => '+',
             LineStatus::Modified => '~',
+            LineStatus::TypeChanged => '≠',
+            LineStatus::Renamed => '»',
             LineStatus::Error => '!',
             LineStatus::ErrorDescription => '^',
         }
@@ -1,47 +1,63 @@
-use super::color_codes::*;
+use super::color_codes::RESET;
 use super::LineStatus;
+use super::Palette;
+use super::Role;
 
 /// Color codes used to print diff lines of different statuses (see
-/// `LineStatus`)
+/// `LineStatus`), plus the `progress` color used outside any `LineStatus`.
 #[derive(Clone)]
 pub struct LineStatusColorCodes {
-    pub deleted: &'static [u8],
-    pub added: &'static [u8],
-    pub modified: &'static [u8],
-    pub error: &'static [u8],
+    pub deleted: Vec<u8>,
+    pub added: Vec<u8>,
+    pub modified: Vec<u8>,
+    pub type_changed: Vec<u8>,
+    pub renamed: Vec<u8>,
+    pub error: Vec<u8>,
+    pub context: Vec<u8>,
+    pub progress: Vec<u8>,
 
     /// Resets the foreground color to its original value.
-    pub reset: &'static [u8],
+    pub reset: Vec<u8>,
 }
 
 impl LineStatusColorCodes {
     pub fn no_color() -> Self {
         Self {
-            deleted: b"",
-            added: b"",
-            modified: b"",
-            error: b"",
-            reset: b"",
+            deleted: Vec::new(),
+            added: Vec::new(),
+            modified: Vec::new(),
+            type_changed: Vec::new(),
+            renamed: Vec::new(),
+            error: Vec::new(),
+            context: Vec::new(),
+            progress: Vec::new(),
+            reset: Vec::new(),
         }
     }
 
-    pub fn color() -> Self {
+    pub fn color(palette: &Palette) -> Self {
         Self {
-            deleted: YELLOW,
-            added: GREEN,
-            modified: BLUE,
-            error: RED,
-            reset: RESET,
+            deleted: palette.get(Role::Removed).escape_code(),
+            added: palette.get(Role::Added).escape_code(),
+            modified: palette.get(Role::Changed).escape_code(),
+            type_changed: palette.get(Role::TypeChanged).escape_code(),
+            renamed: palette.get(Role::Renamed).escape_code(),
+            error: palette.get(Role::Error).escape_code(),
+            context: palette.get(Role::Context).escape_code(),
+            progress: palette.get(Role::Progress).escape_code(),
+            reset: RESET.to_vec(),
         }
     }
 
-    pub fn get(&self, status: LineStatus) -> &'static [u8] {
+    pub fn get(&self, status: LineStatus) -> &[u8] {
         match status {
-            LineStatus::Deleted => self.deleted,
-            LineStatus::Added => self.added,
-            LineStatus::Modified => self.modified,
-            LineStatus::Error => self.error,
-            LineStatus::ErrorDescription => self.error,
+            LineStatus::Deleted => &self.deleted,
+            LineStatus::Added => &self.added,
+            LineStatus::Modified => &self.modified,
+            LineStatus::TypeChanged => &self.type_changed,
+            LineStatus::Renamed => &self.renamed,
+            LineStatus::Error => &self.error,
+            LineStatus::ErrorDescription => &self.context,
         }
     }
 }
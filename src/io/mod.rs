@@ -1,10 +1,11 @@
-#[allow(dead_code)]
 mod color_codes;
 mod line_status;
 mod line_status_color_codes;
 mod manual_buf_writer;
 mod output_record;
+mod palette;
 mod print_diff;
+mod printer_setup;
 mod progress_status;
 mod read_int_mitigator;
 mod record_printer;
@@ -14,7 +15,9 @@ use self::output_record::*;
 pub use self::line_status::*;
 pub use self::line_status_color_codes::*;
 pub use self::manual_buf_writer::*;
+pub use self::palette::*;
 pub use self::print_diff::*;
+pub use self::printer_setup::*;
 pub use self::progress_status::*;
 pub use self::read_int_mitigator::*;
 pub use self::record_printer::*;
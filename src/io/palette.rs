@@ -0,0 +1,304 @@
+use std::io;
+
+use super::color_codes;
+
+/// A role a themeable color is assigned to.
+///
+/// `LineStatus`'s `Renamed`/`Error` map straight onto `Renamed`/`Error` here; its `Deleted`/
+/// `Added`/`Modified` map onto the more palette-friendly `Removed`/`Added`/`Changed`;
+/// `ErrorDescription` (the explanatory "^" line under an "!" line) uses `Context`. `TypeChanged`
+/// colors the rare case where both sides exist but as different entry types. `Progress` colors
+/// the `ProgressiveRecordPrinter`'s percentage report, which isn't a `LineStatus` at all.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    Added,
+    Removed,
+    Changed,
+    TypeChanged,
+    Renamed,
+    Error,
+    Context,
+    Progress,
+}
+
+impl Role {
+    const ALL: [Role; 8] = [
+        Role::Added,
+        Role::Removed,
+        Role::Changed,
+        Role::TypeChanged,
+        Role::Renamed,
+        Role::Error,
+        Role::Context,
+        Role::Progress,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Role::Added => "added",
+            Role::Removed => "removed",
+            Role::Changed => "changed",
+            Role::TypeChanged => "type_changed",
+            Role::Renamed => "renamed",
+            Role::Error => "error",
+            Role::Context => "context",
+            Role::Progress => "progress",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Role> {
+        Self::ALL.iter().copied().find(|r| r.name() == name)
+    }
+}
+
+/// A single resolved color: either one of the 8 standard VT100 colors, a 256-color palette index,
+/// or a 24-bit RGB triple.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorSpec {
+    Named(NamedColor),
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl ColorSpec {
+    /// Renders the VT100 SGR escape sequence selecting this color as the foreground color.
+    pub fn escape_code(self) -> Vec<u8> {
+        match self {
+            ColorSpec::Named(c) => c.escape_code().to_vec(),
+            ColorSpec::Indexed(n) => format!("\x1B[38;5;{}m", n).into_bytes(),
+            ColorSpec::Rgb(r, g, b) => format!("\x1B[38;2;{};{};{}m", r, g, b).into_bytes(),
+        }
+    }
+
+    /// Parses one `spec` (the part of a `role=spec` pair after the `=`): a named color, `256;N`,
+    /// or `rgb:RR/GG/BB` (hex).
+    fn parse(spec: &str) -> Result<ColorSpec, String> {
+        if let Some(named) = NamedColor::from_name(spec) {
+            return Ok(ColorSpec::Named(named));
+        }
+
+        if let Some(index) = spec.strip_prefix("256;") {
+            return index
+                .parse::<u8>()
+                .map(ColorSpec::Indexed)
+                .map_err(|_| format!("invalid 256-color index: {}", index));
+        }
+
+        if let Some(rgb) = spec.strip_prefix("rgb:") {
+            let channels: Vec<&str> = rgb.split('/').collect();
+
+            if let [r, g, b] = channels[..] {
+                let parse_channel = |s: &str| u8::from_str_radix(s, 16);
+
+                if let (Ok(r), Ok(g), Ok(b)) = (parse_channel(r), parse_channel(g), parse_channel(b))
+                {
+                    return Ok(ColorSpec::Rgb(r, g, b));
+                }
+            }
+
+            return Err(format!("invalid rgb:RR/GG/BB spec: rgb:{}", rgb));
+        }
+
+        Err(format!("unrecognized color spec: {}", spec))
+    }
+}
+
+/// One of the 8 standard VT100 foreground colors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NamedColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl NamedColor {
+    fn from_name(name: &str) -> Option<NamedColor> {
+        match name {
+            "black" => Some(NamedColor::Black),
+            "red" => Some(NamedColor::Red),
+            "green" => Some(NamedColor::Green),
+            "yellow" => Some(NamedColor::Yellow),
+            "blue" => Some(NamedColor::Blue),
+            "magenta" => Some(NamedColor::Magenta),
+            "cyan" => Some(NamedColor::Cyan),
+            "white" => Some(NamedColor::White),
+            _ => None,
+        }
+    }
+
+    fn escape_code(self) -> &'static [u8] {
+        match self {
+            NamedColor::Black => color_codes::BLACK,
+            NamedColor::Red => color_codes::RED,
+            NamedColor::Green => color_codes::GREEN,
+            NamedColor::Yellow => color_codes::YELLOW,
+            NamedColor::Blue => color_codes::BLUE,
+            NamedColor::Magenta => color_codes::MAGENTA,
+            NamedColor::Cyan => color_codes::CYAN,
+            NamedColor::White => color_codes::WHITE,
+        }
+    }
+}
+
+/// Assigns a `ColorSpec` to every `Role`, resolved from `--palette`/`DURDUFF_COLORS` overrides
+/// layered onto `Palette::default()`.
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    added: ColorSpec,
+    removed: ColorSpec,
+    changed: ColorSpec,
+    type_changed: ColorSpec,
+    renamed: ColorSpec,
+    error: ColorSpec,
+    context: ColorSpec,
+    progress: ColorSpec,
+}
+
+impl Palette {
+    /// The built-in colors, used for every role no `--palette`/`DURDUFF_COLORS` override sets.
+    pub fn default() -> Self {
+        Self {
+            added: ColorSpec::Named(NamedColor::Green),
+            removed: ColorSpec::Named(NamedColor::Yellow),
+            changed: ColorSpec::Named(NamedColor::Blue),
+            type_changed: ColorSpec::Named(NamedColor::Black),
+            renamed: ColorSpec::Named(NamedColor::Magenta),
+            error: ColorSpec::Named(NamedColor::Red),
+            context: ColorSpec::Named(NamedColor::Cyan),
+            progress: ColorSpec::Named(NamedColor::White),
+        }
+    }
+
+    pub fn get(&self, role: Role) -> ColorSpec {
+        match role {
+            Role::Added => self.added,
+            Role::Removed => self.removed,
+            Role::Changed => self.changed,
+            Role::TypeChanged => self.type_changed,
+            Role::Renamed => self.renamed,
+            Role::Error => self.error,
+            Role::Context => self.context,
+            Role::Progress => self.progress,
+        }
+    }
+
+    fn set(&mut self, role: Role, spec: ColorSpec) {
+        match role {
+            Role::Added => self.added = spec,
+            Role::Removed => self.removed = spec,
+            Role::Changed => self.changed = spec,
+            Role::TypeChanged => self.type_changed = spec,
+            Role::Renamed => self.renamed = spec,
+            Role::Error => self.error = spec,
+            Role::Context => self.context = spec,
+            Role::Progress => self.progress = spec,
+        }
+    }
+
+    /// Parses an `LS_COLORS`-style, colon-separated `role=spec` list, overriding the
+    /// corresponding roles of `self`.
+    pub fn apply(&mut self, spec: &str) -> io::Result<()> {
+        for pair in spec.split(':').filter(|p| !p.is_empty()) {
+            let (role, color) = pair.split_once('=').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("palette entry missing '=': {}", pair),
+                )
+            })?;
+
+            let role = Role::from_name(role).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("unknown palette role: {}", role))
+            })?;
+
+            let color = ColorSpec::parse(color).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("palette role {}: {}", role.name(), e))
+            })?;
+
+            self.set(role, color);
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves the effective `Palette`: `Palette::default()`, with `--palette` (or, if that's unset,
+/// `DURDUFF_COLORS`) applied on top.
+pub fn resolve_palette(cli_palette: Option<&str>) -> io::Result<Palette> {
+    let mut palette = Palette::default();
+
+    let env_palette;
+    let spec = match cli_palette {
+        Some(s) => Some(s),
+        None => {
+            env_palette = std::env::var("DURDUFF_COLORS").ok();
+            env_palette.as_deref()
+        }
+    };
+
+    if let Some(spec) = spec {
+        palette.apply(spec)?;
+    }
+
+    Ok(palette)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_color() {
+        assert_eq!(
+            ColorSpec::parse("red").unwrap(),
+            ColorSpec::Named(NamedColor::Red)
+        );
+    }
+
+    #[test]
+    fn parses_256_color() {
+        assert_eq!(ColorSpec::parse("256;201").unwrap(), ColorSpec::Indexed(201));
+    }
+
+    #[test]
+    fn parses_rgb_color() {
+        assert_eq!(
+            ColorSpec::parse("rgb:ff/00/80").unwrap(),
+            ColorSpec::Rgb(0xff, 0x00, 0x80)
+        );
+    }
+
+    #[test]
+    fn rejects_unrecognized_spec() {
+        assert!(ColorSpec::parse("chartreuse").is_err());
+    }
+
+    #[test]
+    fn applies_overrides_by_role() {
+        let mut palette = Palette::default();
+
+        palette.apply("added=256;46:progress=rgb:80/80/80").unwrap();
+
+        assert_eq!(palette.get(Role::Added), ColorSpec::Indexed(46));
+        assert_eq!(palette.get(Role::Progress), ColorSpec::Rgb(0x80, 0x80, 0x80));
+        assert_eq!(palette.get(Role::Removed), ColorSpec::Named(NamedColor::Yellow));
+    }
+
+    #[test]
+    fn rejects_unknown_role() {
+        let mut palette = Palette::default();
+
+        assert!(palette.apply("unknown=red").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_equals() {
+        let mut palette = Palette::default();
+
+        assert!(palette.apply("added").is_err());
+    }
+}
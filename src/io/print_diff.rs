@@ -1,12 +1,14 @@
 use std::io::ErrorKind;
 
-use std::os::unix::ffi::OsStrExt;
-
 use std::path::Path;
 use std::path::PathBuf;
 
 use percent_encoding::{utf8_percent_encode, CONTROLS};
 
+use crate::cli::OutputFormat;
+
+use crate::platform::os_str_to_bytes;
+
 use crate::verdict::Verdict;
 
 use super::LineStatus;
@@ -57,7 +59,7 @@ fn wrap_blob_in_record(setup: &OutputSetup, status: LineStatus, blob: &[u8]) ->
         setup.color_codes.get(status),
         &prefix,
         blob,
-        setup.color_codes.reset,
+        &setup.color_codes.reset,
         setup.line_terminator,
     ];
 
@@ -86,10 +88,12 @@ fn verdict_and_blob_to_output_record(
     let mut stderr = Vec::new();
 
     let status = match verdict {
-        Verdict::Same => std::unreachable!(),
+        Verdict::Same | Verdict::Ignored => std::unreachable!(),
         Verdict::Deleted => LineStatus::Deleted,
         Verdict::Added => LineStatus::Added,
-        Verdict::Modified => LineStatus::Modified,
+        Verdict::Modified(_) => LineStatus::Modified,
+        Verdict::TypeChanged => LineStatus::TypeChanged,
+        Verdict::Renamed(_) => LineStatus::Renamed,
         Verdict::Error(ek) => {
             stderr = error_kind_to_stderr_record(&setup, ek);
             LineStatus::Error
@@ -107,12 +111,20 @@ fn verdict_and_path_to_percent_output_record(
     setup: &OutputSetup,
     (verdict, path): (Verdict, PathBuf),
 ) -> OutputRecord {
-    if verdict == Verdict::Same {
-        OutputRecord::empty()
-    } else {
-        let percent_path = utf8_percent_encode_path(&path);
-        verdict_and_blob_to_output_record(setup, verdict, percent_path.as_bytes())
+    if verdict == Verdict::Same || verdict == Verdict::Ignored {
+        return OutputRecord::empty();
     }
+
+    let blob = match &verdict {
+        Verdict::Renamed(old_path) => format!(
+            "{} -> {}",
+            utf8_percent_encode_path(old_path),
+            utf8_percent_encode_path(&path)
+        ),
+        _ => utf8_percent_encode_path(&path),
+    };
+
+    verdict_and_blob_to_output_record(setup, verdict, blob.as_bytes())
 }
 
 /// Converts `(verdict, path)` into an `OutputRecord` according to `OutputSetup`.
@@ -121,11 +133,117 @@ fn verdict_and_path_to_raw_output_record(
     setup: &OutputSetup,
     (verdict, path): (Verdict, PathBuf),
 ) -> OutputRecord {
-    if verdict == Verdict::Same {
-        OutputRecord::empty()
-    } else {
-        let path_blob = path.as_os_str().as_bytes();
-        verdict_and_blob_to_output_record(setup, verdict, path_blob)
+    if verdict == Verdict::Same || verdict == Verdict::Ignored {
+        return OutputRecord::empty();
+    }
+
+    let blob = match &verdict {
+        Verdict::Renamed(old_path) => {
+            let mut blob = os_str_to_bytes(old_path.as_os_str());
+            blob.extend_from_slice(b" -> ");
+            blob.extend_from_slice(&os_str_to_bytes(path.as_os_str()));
+            blob
+        }
+        _ => os_str_to_bytes(path.as_os_str()),
+    };
+
+    verdict_and_blob_to_output_record(setup, verdict, &blob)
+}
+
+/// Returns the `status` field durduff's JSON output uses for `verdict`.
+fn verdict_to_json_status(verdict: &Verdict) -> &'static str {
+    match verdict {
+        Verdict::Same | Verdict::Ignored => std::unreachable!(),
+        Verdict::Deleted => "deleted",
+        Verdict::Added => "added",
+        Verdict::Modified(_) => "modified",
+        Verdict::TypeChanged => "type-changed",
+        Verdict::Renamed(_) => "renamed",
+        Verdict::Error(_) => "error",
+    }
+}
+
+/// Encodes `path` the same way `verdict_and_path_to_json_output_record` encodes its main `path`
+/// field: UTF-8 where possible, falling back to percent-encoding.
+fn encode_path_for_json(path: &Path) -> (String, &'static str) {
+    match path.to_str() {
+        Some(s) => (s.to_string(), "utf8"),
+        None => (utf8_percent_encode_path(path), "percent"),
+    }
+}
+
+/// Minimally JSON-escapes and quotes `s`.
+fn json_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+/// Converts `(verdict, path)` into a one-line JSON `OutputRecord`.
+///
+/// `path` is emitted UTF-8 if possible; otherwise it's percent-encoded, and `path_encoding`
+/// records which of the two happened, so consumers know whether to percent-decode it. `error` is
+/// always present, `null` unless `verdict` is `Error`, so consumers can rely on the field existing
+/// rather than checking `status` first. `byte_offset` is the offset of the first differing byte,
+/// present only for a `Modified` verdict whose comparison mode discovered one (see
+/// `Verdict::Modified`'s doc comment), `null` otherwise. Renamed records additionally carry
+/// `old_path`/`old_path_encoding`, encoded the same way.
+fn verdict_and_path_to_json_output_record(
+    (verdict, path): (Verdict, PathBuf),
+) -> OutputRecord {
+    if verdict == Verdict::Same || verdict == Verdict::Ignored {
+        return OutputRecord::empty();
+    }
+
+    let (path_value, path_encoding) = encode_path_for_json(&path);
+
+    let error_value = match &verdict {
+        Verdict::Error(ek) => json_quote(fmt_error_kind(*ek)),
+        _ => "null".to_string(),
+    };
+
+    let byte_offset_value = match &verdict {
+        Verdict::Modified(Some(offset)) => offset.to_string(),
+        _ => "null".to_string(),
+    };
+
+    let mut line = format!(
+        "{{\"status\":{},\"path\":{},\"path_encoding\":{},\"byte_offset\":{},\"error\":{}",
+        json_quote(verdict_to_json_status(&verdict)),
+        json_quote(&path_value),
+        json_quote(path_encoding),
+        byte_offset_value,
+        error_value,
+    );
+
+    if let Verdict::Renamed(old_path) = &verdict {
+        let (old_path_value, old_path_encoding) = encode_path_for_json(old_path);
+        line.push_str(&format!(
+            ",\"old_path\":{},\"old_path_encoding\":{}",
+            json_quote(&old_path_value),
+            json_quote(old_path_encoding),
+        ));
+    }
+
+    line.push_str("}\n");
+
+    OutputRecord {
+        stdout: line.into_bytes(),
+        stderr: Vec::new(),
     }
 }
 
@@ -142,16 +260,150 @@ where
     record_printer.finish();
 }
 
+/// Tallies per-status counts of diff records, for the summary object JSON/NDJSON output ends
+/// with.
+#[derive(Default)]
+struct JsonSummary {
+    added: u64,
+    deleted: u64,
+    modified: u64,
+    type_changed: u64,
+    renamed: u64,
+    errors: u64,
+}
+
+impl JsonSummary {
+    fn observe(&mut self, verdict: &Verdict) {
+        match verdict {
+            Verdict::Added => self.added += 1,
+            Verdict::Deleted => self.deleted += 1,
+            Verdict::Modified(_) => self.modified += 1,
+            Verdict::TypeChanged => self.type_changed += 1,
+            Verdict::Renamed(_) => self.renamed += 1,
+            Verdict::Error(_) => self.errors += 1,
+            Verdict::Same | Verdict::Ignored => (),
+        }
+    }
+
+    /// Returns the bare `{"added":N,...}` JSON object, with no enclosing `"summary"` key or
+    /// trailing newline, so it can be embedded in a larger document.
+    fn to_json_object(&self) -> String {
+        format!(
+            "{{\"added\":{},\"deleted\":{},\"modified\":{},\"type-changed\":{},\
+             \"renamed\":{},\"errors\":{}}}",
+            self.added, self.deleted, self.modified, self.type_changed, self.renamed, self.errors,
+        )
+    }
+
+    fn to_output_record(&self) -> OutputRecord {
+        let line = format!("{{\"summary\":{}}}\n", self.to_json_object());
+
+        OutputRecord {
+            stdout: line.into_bytes(),
+            stderr: Vec::new(),
+        }
+    }
+}
+
+/// Prints `verdicts` as NDJSON: one JSON object per line, tallying a trailing `JsonSummary`
+/// record as it goes. When `brief`, per-entry records are replaced by empty ones (still driving
+/// `record_printer`'s progress reporting), so only the final summary line carries any content.
+fn print_all_ndjson_records<I, P>(verdicts: I, brief: bool, record_printer: &mut P)
+where
+    I: Iterator<Item = (Verdict, PathBuf)>,
+    P: RecordPrinter,
+{
+    let mut summary = JsonSummary::default();
+    let mut verdicts = verdicts.peekable();
+
+    while let Some((verdict, path)) = verdicts.next() {
+        summary.observe(&verdict);
+
+        let remaining = verdicts.size_hint().0;
+
+        let record = if brief {
+            OutputRecord::empty()
+        } else {
+            verdict_and_path_to_json_output_record((verdict, path))
+        };
+
+        record_printer.print(&record, remaining);
+    }
+
+    record_printer.print(&summary.to_output_record(), 0);
+    record_printer.finish();
+}
+
+/// Prints `verdicts` as a single JSON document: `{"records":[...],"summary":{...}}`. Unlike
+/// NDJSON, a bare concatenation of `{...}\n{...}\n` objects isn't parseable by a standard JSON
+/// parser as one document, so this buffers each record's JSON text and comma-joins it into a
+/// `records` array instead of streaming it. `record_printer` still sees one `print` call per
+/// verdict (with an empty record) so progress reporting stays accurate; the buffered document is
+/// emitted as the final, and only, non-empty record. When `brief`, the `records` array is left
+/// empty and only `summary` is populated.
+fn print_all_json_document<I, P>(verdicts: I, brief: bool, record_printer: &mut P)
+where
+    I: Iterator<Item = (Verdict, PathBuf)>,
+    P: RecordPrinter,
+{
+    let mut summary = JsonSummary::default();
+    let mut records_json = Vec::new();
+    let mut verdicts = verdicts.peekable();
+
+    while let Some((verdict, path)) = verdicts.next() {
+        summary.observe(&verdict);
+
+        let remaining = verdicts.size_hint().0;
+
+        if !brief {
+            let record = verdict_and_path_to_json_output_record((verdict, path));
+
+            if !record.stdout.is_empty() {
+                let mut line = record.stdout;
+                line.pop(); // drop the trailing '\n'; the array comma-joins instead
+                records_json.push(String::from_utf8(line).unwrap());
+            }
+        }
+
+        record_printer.print(&OutputRecord::empty(), remaining);
+    }
+
+    let document = format!(
+        "{{\"records\":[{}],\"summary\":{}}}\n",
+        records_json.join(","),
+        summary.to_json_object(),
+    );
+
+    record_printer.print(
+        &OutputRecord {
+            stdout: document.into_bytes(),
+            stderr: Vec::new(),
+        },
+        0,
+    );
+    record_printer.finish();
+}
+
 /// Print diff from `verdicts` as specified by `args`
 pub fn print_diff<I, P>(
     verdicts: I,
     mut record_printer: P,
     color_codes: LineStatusColorCodes,
     nul_terminated: bool,
+    format: OutputFormat,
+    brief: bool,
 ) where
     I: Iterator<Item = (Verdict, PathBuf)>,
     P: RecordPrinter,
 {
+    match format {
+        OutputFormat::Ndjson => {
+            return print_all_ndjson_records(verdicts, brief, &mut record_printer)
+        }
+        OutputFormat::Json => return print_all_json_document(verdicts, brief, &mut record_printer),
+        OutputFormat::Text => (),
+    }
+
     let line_terminator: &'static [u8] = if nul_terminated { b"\x00" } else { b"\n" };
 
     let output_setup = OutputSetup {
@@ -159,12 +411,12 @@ pub fn print_diff<I, P>(
         line_terminator,
     };
 
-    let verdict_and_path_to_output_record = |vp| {
-        if nul_terminated {
+    let verdict_and_path_to_output_record = |vp| match format {
+        OutputFormat::Json | OutputFormat::Ndjson => std::unreachable!(),
+        OutputFormat::Text if nul_terminated => {
             verdict_and_path_to_raw_output_record(&output_setup, vp)
-        } else {
-            verdict_and_path_to_percent_output_record(&output_setup, vp)
         }
+        OutputFormat::Text => verdict_and_path_to_percent_output_record(&output_setup, vp),
     };
 
     let output_records = verdicts.map(verdict_and_path_to_output_record);
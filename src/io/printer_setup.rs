@@ -0,0 +1,131 @@
+use std::env;
+
+use crate::cli::TtyEnabledOutput;
+
+use super::LineStatusColorCodes;
+use super::Palette;
+
+/// Decides whether the progress-reporting printer should be used, given `--progress` and whether
+/// stderr (where progress reports, and their VT100 cursor-movement escapes, are written) is a
+/// TTY. This keeps the escapes from leaking into a file or pipe when `--progress` is left at its
+/// default of `auto`.
+pub fn use_progressive_printer(progress: TtyEnabledOutput, stderr_is_tty: bool) -> bool {
+    match progress {
+        TtyEnabledOutput::Never => false,
+        TtyEnabledOutput::Auto => stderr_is_tty,
+        TtyEnabledOutput::Always => true,
+    }
+}
+
+/// Whether `CLICOLOR_FORCE` (https://bixense.com/clicolor/) asks for color regardless of whether
+/// stdout is a TTY.
+fn clicolor_forced() -> bool {
+    env::var_os("CLICOLOR_FORCE").map_or(false, |v| v != "0")
+}
+
+/// Whether `CLICOLOR` (https://bixense.com/clicolor/) is explicitly set to `0`, asking for no
+/// color even when stdout is a TTY.
+fn clicolor_disabled() -> bool {
+    env::var_os("CLICOLOR").map_or(false, |v| v == "0")
+}
+
+/// Decides which `LineStatusColorCodes` to use, given `--color`, `palette`, whether stdout is a
+/// TTY, and the `NO_COLOR` (https://no-color.org/) and `CLICOLOR`/`CLICOLOR_FORCE`
+/// (https://bixense.com/clicolor/) environment variables. In `auto` mode: `NO_COLOR` always
+/// disables color; otherwise `CLICOLOR_FORCE` always enables it; otherwise color is used when
+/// stdout is a TTY and `CLICOLOR` isn't `0`. `--color always`/`--color never` override all of the
+/// above.
+pub fn resolve_color_codes(
+    color: TtyEnabledOutput,
+    stdout_is_tty: bool,
+    palette: &Palette,
+) -> LineStatusColorCodes {
+    match color {
+        TtyEnabledOutput::Never => LineStatusColorCodes::no_color(),
+        TtyEnabledOutput::Always => LineStatusColorCodes::color(palette),
+        TtyEnabledOutput::Auto => {
+            let use_color = if env::var_os("NO_COLOR").is_some() {
+                false
+            } else if clicolor_forced() {
+                true
+            } else {
+                stdout_is_tty && !clicolor_disabled()
+            };
+
+            if use_color {
+                LineStatusColorCodes::color(palette)
+            } else {
+                LineStatusColorCodes::no_color()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // Serializes tests that mutate `NO_COLOR`, since env vars are process-global but tests run
+    // concurrently.
+    static NO_COLOR_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn progress_never_is_always_off() {
+        assert!(!use_progressive_printer(TtyEnabledOutput::Never, true));
+        assert!(!use_progressive_printer(TtyEnabledOutput::Never, false));
+    }
+
+    #[test]
+    fn progress_always_is_always_on() {
+        assert!(use_progressive_printer(TtyEnabledOutput::Always, true));
+        assert!(use_progressive_printer(TtyEnabledOutput::Always, false));
+    }
+
+    #[test]
+    fn progress_auto_follows_stderr_tty() {
+        assert!(use_progressive_printer(TtyEnabledOutput::Auto, true));
+        assert!(!use_progressive_printer(TtyEnabledOutput::Auto, false));
+    }
+
+    #[test]
+    fn color_never_ignores_tty_and_no_color() {
+        assert_eq!(
+            resolve_color_codes(TtyEnabledOutput::Never, true, &Palette::default()).reset,
+            LineStatusColorCodes::no_color().reset
+        );
+    }
+
+    #[test]
+    fn color_always_ignores_tty() {
+        assert_eq!(
+            resolve_color_codes(TtyEnabledOutput::Always, false, &Palette::default()).reset,
+            LineStatusColorCodes::color(&Palette::default()).reset
+        );
+    }
+
+    #[test]
+    fn color_auto_is_off_without_a_tty() {
+        assert_eq!(
+            resolve_color_codes(TtyEnabledOutput::Auto, false, &Palette::default()).reset,
+            LineStatusColorCodes::no_color().reset
+        );
+    }
+
+    #[test]
+    fn color_auto_respects_no_color_even_with_a_tty() {
+        let _guard = NO_COLOR_LOCK.lock().unwrap();
+        let previous = env::var_os("NO_COLOR");
+        env::set_var("NO_COLOR", "1");
+
+        let result = resolve_color_codes(TtyEnabledOutput::Auto, true, &Palette::default());
+
+        match previous {
+            Some(v) => env::set_var("NO_COLOR", v),
+            None => env::remove_var("NO_COLOR"),
+        }
+
+        assert_eq!(result.reset, LineStatusColorCodes::no_color().reset);
+    }
+}
@@ -47,6 +47,10 @@ where
 
     status: ProgressStatus,
     last_percent: u32,
+
+    /// `Role::Progress`'s color codes (empty when color is off).
+    progress_color: Vec<u8>,
+    reset_color: Vec<u8>,
 }
 
 impl<O, E> ProgressiveRecordPrinter<O, E>
@@ -54,8 +58,15 @@ where
     O: Write,
     E: Write,
 {
-    /// Constructor. `total_hint` is used for the denominator in progress reports.
-    pub fn new(stdout: O, stderr: E, total_hint: usize) -> Self {
+    /// Constructor. `total_hint` is used for the denominator in progress reports. `progress_color`
+    /// and `reset_color` wrap the percentage report (pass empty `Vec`s for uncolored output).
+    pub fn new(
+        stdout: O,
+        stderr: E,
+        total_hint: usize,
+        progress_color: Vec<u8>,
+        reset_color: Vec<u8>,
+    ) -> Self {
         Self {
             stdout: ManualBufWriter::new(stdout, 2 * BYTES_PER_FLUSH),
             stderr: ManualBufWriter::new(stderr, BYTES_PER_FLUSH),
@@ -66,6 +77,9 @@ where
             },
 
             last_percent: 0,
+
+            progress_color,
+            reset_color,
         }
     }
 }
@@ -96,12 +110,14 @@ where
         self.stdout.flush().unwrap();
 
         self.stderr.write_all(VT100_SAVE_CURSOR).unwrap();
+        self.stderr.write_all(&self.progress_color).unwrap();
         write!(
             &mut self.stderr,
             "Files processed: {}/{} ({}%)",
             self.status.processed_no, self.status.total_no, cur_percent
         )
         .unwrap();
+        self.stderr.write_all(&self.reset_color).unwrap();
 
         // now we print the progress report
         self.stderr.flush().unwrap();
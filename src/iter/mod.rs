@@ -0,0 +1,9 @@
+mod ok_iter;
+mod par_verdict_iter;
+mod rec_dir_iter;
+mod sum_iter;
+
+pub use self::ok_iter::*;
+pub use self::par_verdict_iter::*;
+pub use self::rec_dir_iter::*;
+pub use self::sum_iter::*;
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use std::iter::Iterator;
+
+use std::path::PathBuf;
+
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use std::thread;
+
+use super::SumIterSelector;
+
+use crate::verdict::Verdict;
+
+/// Bounds how many dispatched-but-not-yet-consumed items (in flight to workers, or buffered
+/// out-of-order results waiting for their turn) may accumulate, so a fast worker can't race
+/// arbitrarily far ahead of a slow one and exhaust memory.
+const MAX_PENDING: usize = 4096;
+
+/// Runs `get_verdict` over `items` across `jobs` worker threads, yielding results in the exact
+/// order `items` produced them (the order `print_diff` relies on for `cmp_paths`-sorted output).
+///
+/// Dispatch and results both flow through bounded channels, so a slow consumer (or a results
+/// buffer that's outrun the slowest worker) stalls dispatch instead of growing unboundedly.
+pub struct ParVerdictIter {
+    results: Receiver<(usize, (Verdict, PathBuf))>,
+    pending: BTreeMap<usize, (Verdict, PathBuf)>,
+    next_index: usize,
+
+    /// One token per dispatched-but-not-yet-yielded item, at most `MAX_PENDING` of them: the
+    /// dispatch thread sends one before each item and `next()` takes one back per yielded result.
+    permits: Receiver<()>,
+}
+
+impl ParVerdictIter {
+    /// `jobs == 1` runs every `get_verdict` call on a single worker thread, one at a time and in
+    /// order, preserving the exact sequential behavior and error semantics.
+    pub fn new<I, F>(items: I, get_verdict: F, jobs: usize) -> Self
+    where
+        I: Iterator<Item = (SumIterSelector, PathBuf)> + Send + 'static,
+        F: Fn((SumIterSelector, PathBuf)) -> (Verdict, PathBuf) + Clone + Send + 'static,
+    {
+        let jobs = jobs.max(1);
+
+        let (work_tx, work_rx) = mpsc::sync_channel::<(usize, (SumIterSelector, PathBuf))>(MAX_PENDING);
+        let work_rx = Arc::new(Mutex::new(work_rx));
+
+        let (result_tx, result_rx) = mpsc::sync_channel(MAX_PENDING);
+
+        for _ in 0..jobs {
+            let work_rx = Arc::clone(&work_rx);
+            let result_tx = result_tx.clone();
+            let get_verdict = get_verdict.clone();
+
+            thread::spawn(move || loop {
+                let next = work_rx.lock().unwrap().recv();
+
+                match next {
+                    Ok((index, item)) => {
+                        if result_tx.send((index, get_verdict(item))).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            });
+        }
+
+        // Dropping our own sender lets `result_rx` see EOF once every worker's clone has dropped
+        // theirs.
+        drop(result_tx);
+
+        let (permit_tx, permit_rx) = mpsc::sync_channel::<()>(MAX_PENDING);
+
+        thread::spawn(move || {
+            for (index, item) in items.enumerate() {
+                if permit_tx.send(()).is_err() || work_tx.send((index, item)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            results: result_rx,
+            pending: BTreeMap::new(),
+            next_index: 0,
+            permits: permit_rx,
+        }
+    }
+}
+
+impl Iterator for ParVerdictIter {
+    type Item = (Verdict, PathBuf);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(v) = self.pending.remove(&self.next_index) {
+                self.next_index += 1;
+                let _ = self.permits.try_recv();
+                return Some(v);
+            }
+
+            match self.results.recv() {
+                Ok((index, v)) => {
+                    self.pending.insert(index, v);
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    fn pb(s: &str) -> PathBuf {
+        Path::new(s).to_path_buf()
+    }
+
+    #[test]
+    fn preserves_order_across_multiple_jobs() {
+        let items: Vec<_> = (0..200)
+            .map(|i| (SumIterSelector::Both, pb(&i.to_string())))
+            .collect();
+
+        let expected: Vec<_> = items
+            .iter()
+            .map(|(_, p)| (Verdict::Same, p.clone()))
+            .collect();
+
+        let result: Vec<_> = ParVerdictIter::new(
+            items.into_iter(),
+            |(_, path)| (Verdict::Same, path),
+            8,
+        )
+        .collect();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn single_job_matches_sequential_behavior() {
+        let items: Vec<_> = (0..20)
+            .map(|i| (SumIterSelector::Both, pb(&i.to_string())))
+            .collect();
+
+        let expected: Vec<_> = items
+            .iter()
+            .map(|(_, p)| (Verdict::Modified(None), p.clone()))
+            .collect();
+
+        let result: Vec<_> =
+            ParVerdictIter::new(items.into_iter(), |(_, path)| (Verdict::Modified(None), path), 1)
+                .collect();
+
+        assert_eq!(result, expected);
+    }
+}
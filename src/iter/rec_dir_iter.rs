@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 
+use std::collections::HashSet;
 use std::collections::VecDeque;
 
 use std::convert::TryFrom;
@@ -8,9 +9,16 @@ use std::io;
 
 use std::iter::Iterator;
 
+use std::os::unix::fs::MetadataExt;
+
 use std::path::Path;
 use std::path::PathBuf;
 
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use std::thread;
+
 use crate::io::utf8_percent_encode_path;
 
 /// Compares paths, so that:
@@ -26,10 +34,28 @@ pub fn cmp_paths(lhs: &PathBuf, rhs: &PathBuf) -> Ordering {
 /// Recursive directory iterator
 ///
 /// It yields paths in the order given by `cmp_paths`.
+///
+/// Internally, it works one "generation" (a `cmp_paths` tier, i.e. all paths sharing a given
+/// component count) at a time: `ready` holds the generation currently being handed out by
+/// `next()`, while `pending` holds the not-yet-expanded paths that make up the generation after
+/// that. When `threads > 1`, expanding a generation's entries (the `stat` + `read_dir` pair done
+/// per entry) is spread across worker threads, since a generation's entries are independent of
+/// each other.
 pub struct RecDirIter {
     top: PathBuf,
-    to_traverse: VecDeque<PathBuf>,
+    follow_symlinks: bool,
+    threads: usize,
+
+    /// `(st_dev, st_ino)` of every directory already descended into, so a symlink cycle (when
+    /// `follow_symlinks` is set) gets reported once but never recursed into again. Unused (and
+    /// left empty) when `follow_symlinks` is off, since `symlink_metadata` never descends through
+    /// a symlink in the first place.
+    visited_dirs: HashSet<(u64, u64)>,
+
+    ready: VecDeque<PathBuf>,
+    pending: Vec<PathBuf>,
     error: Option<io::Error>,
+    done: bool,
 }
 
 /// How many elements we expect a directory may have at most
@@ -37,11 +63,36 @@ pub struct RecDirIter {
 /// It's just a performance hint. The program won't break in cases where it's not true.
 const DIR_ELEMS_MAX: usize = 4 << 10;
 
-fn try_append_dir_elems(dst: &mut VecDeque<PathBuf>, top: &Path, dir: &Path) -> io::Result<()> {
-    let full_prefix = top.join(dir);
+/// Expands a single entry `path` (relative to `top`): if it's a directory, returns its children
+/// (relative to `top`, sorted by file name); otherwise returns an empty `Vec`.
+///
+/// This is the per-entry unit of work `RecDirIter` fans out across `threads` worker threads.
+fn expand_entry(
+    top: &Path,
+    path: &Path,
+    follow_symlinks: bool,
+    visited_dirs: &Mutex<HashSet<(u64, u64)>>,
+) -> io::Result<Vec<PathBuf>> {
+    let full_prefix = top.join(path);
+
+    let metadata = if follow_symlinks {
+        full_prefix.metadata()?
+    } else {
+        full_prefix.symlink_metadata()?
+    };
+
+    if !metadata.file_type().is_dir() {
+        return Ok(Vec::new());
+    }
+
+    if follow_symlinks {
+        let mut visited_dirs = visited_dirs.lock().unwrap();
 
-    if !full_prefix.symlink_metadata()?.file_type().is_dir() {
-        return Ok(());
+        if !visited_dirs.insert((metadata.dev(), metadata.ino())) {
+            // already entered this directory on the current traversal (a symlink cycle): report
+            // the entry itself (done by the caller) but don't recurse into it again.
+            return Ok(Vec::new());
+        }
     }
 
     let mut elems = Vec::with_capacity(DIR_ELEMS_MAX);
@@ -53,9 +104,7 @@ fn try_append_dir_elems(dst: &mut VecDeque<PathBuf>, top: &Path, dir: &Path) ->
     // the following is a lot faster than either sort_unstable, sort_unstable_by_key, or sort_by_key.
     elems.sort_by_cached_key(|p| p.file_name().unwrap().to_os_string());
 
-    dst.extend(elems.drain(..));
-
-    Ok(())
+    Ok(elems)
 }
 
 /// Replaces the description of `e` with "reading directory " + UTF-8 percent-encoded `p`.
@@ -69,59 +118,145 @@ fn annotate_error(p: &Path, e: io::Error) -> io::Error {
 }
 
 impl RecDirIter {
-    fn try_append_dir_elems(&mut self, dir: &Path) -> io::Result<()> {
-        try_append_dir_elems(&mut self.to_traverse, &self.top, dir)
+    /// Like `TryFrom<PathBuf>`, but lets the caller opt into `--dereference`-style traversal: when
+    /// `follow_symlinks` is set, symlinked subdirectories are descended into (using `metadata`
+    /// instead of `symlink_metadata`), with cycle detection guarding against self-referential
+    /// symlinks.
+    pub fn new(top: PathBuf, follow_symlinks: bool) -> Result<Self, RecDirIterTopIsNotDir> {
+        let top_metadata = if follow_symlinks {
+            top.metadata()
+        } else {
+            top.symlink_metadata()
+        };
+
+        let mut visited_dirs = HashSet::new();
+
+        let (pending, error) = match top_metadata {
+            Ok(m) => {
+                if !m.file_type().is_dir() {
+                    return Err(RecDirIterTopIsNotDir);
+                }
+
+                // Root's `(dev, ino)` isn't pre-inserted here: `expand_entry` below does its own
+                // insert for whatever path it's given, including the root, and would otherwise
+                // see it already present and mistake the root itself for a cycle.
+                let visited_dirs_mutex = Mutex::new(visited_dirs);
+
+                let result = expand_entry(&top, Path::new(""), follow_symlinks, &visited_dirs_mutex);
+
+                visited_dirs = visited_dirs_mutex.into_inner().unwrap();
+
+                match result {
+                    Ok(children) => (children, None),
+                    Err(e) => (Vec::new(), Some(annotate_error(&top, e))),
+                }
+            }
+            Err(e) => (Vec::new(), Some(e)),
+        };
+
+        Ok(Self {
+            top,
+            follow_symlinks,
+            threads: 1,
+            visited_dirs,
+            ready: VecDeque::new(),
+            pending,
+            error,
+            done: false,
+        })
     }
 
-    fn append_dir_elems(&mut self, d: &Path) {
-        if let Err(e) = self.try_append_dir_elems(d) {
-            let err_path = self.top.join(d);
-            self.error = Some(annotate_error(&err_path, e));
-        }
+    /// Scans up to `threads` directories concurrently instead of one at a time.
+    ///
+    /// `threads == 1` (the default) reproduces today's sequential traversal exactly, including
+    /// the order in which errors surface.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
     }
-}
 
-#[derive(Debug)]
-pub struct RecDirIterTopIsNotDir;
+    /// Expands every entry of the generation in `self.pending` (in parallel, if `self.threads >
+    /// 1`), then sets up `self.ready`/`self.pending`/`self.error`/`self.done` for the next calls
+    /// to `next()`.
+    ///
+    /// Mirrors the single-entry-at-a-time sequential semantics exactly: every entry of the
+    /// generation up to and including the first one whose expansion fails is yielded, but every
+    /// entry after it — siblings in this generation and anything queued for the next one — is
+    /// dropped, and the whole traversal ends after reporting that one error.
+    fn advance(&mut self) {
+        let items = std::mem::take(&mut self.pending);
+
+        if items.is_empty() {
+            self.done = true;
+            return;
+        }
 
-impl TryFrom<PathBuf> for RecDirIter {
-    type Error = RecDirIterTopIsNotDir;
+        let visited_dirs = Arc::new(Mutex::new(std::mem::take(&mut self.visited_dirs)));
+        let threads = self.threads.min(items.len());
 
-    fn try_from(top: PathBuf) -> Result<Self, Self::Error> {
-        let mut iter = Self {
-            top,
-            to_traverse: VecDeque::new(),
-            error: None,
+        let results: Vec<io::Result<Vec<PathBuf>>> = if threads <= 1 {
+            items
+                .iter()
+                .map(|p| expand_entry(&self.top, p, self.follow_symlinks, &visited_dirs))
+                .collect()
+        } else {
+            let top = Arc::new(self.top.clone());
+            let follow_symlinks = self.follow_symlinks;
+            let chunk_size = (items.len() + threads - 1) / threads;
+
+            let handles: Vec<_> = items
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let chunk = chunk.to_vec();
+                    let top = Arc::clone(&top);
+                    let visited_dirs = Arc::clone(&visited_dirs);
+
+                    thread::spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|p| expand_entry(&top, p, follow_symlinks, &visited_dirs))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
         };
 
-        let null_path = Path::new("");
+        self.visited_dirs = Arc::try_unwrap(visited_dirs).unwrap().into_inner().unwrap();
 
-        // appease the borrow checker...
-        //
-        // (writing "&iter.top" in the closure conflicts with the "&mut iter.to_traverse" below.
-        // don't ask me why...)
-        let top = &iter.top;
+        match results.iter().position(Result::is_err) {
+            Some(i) => {
+                let e = results.into_iter().nth(i).unwrap().unwrap_err();
 
-        let annot_error = |e: io::Error| annotate_error(&top, e);
+                self.ready = items[..=i].iter().cloned().collect();
+                self.error = Some(annotate_error(&self.top.join(&items[i]), e));
+                self.done = true;
+            }
+            None => {
+                let mut next_gen: Vec<PathBuf> =
+                    results.into_iter().flat_map(Result::unwrap).collect();
 
-        let top_metadata = iter.top.symlink_metadata();
+                next_gen.sort_by(cmp_paths);
 
-        iter.error = match top_metadata {
-            Ok(m) => {
-                let ft = m.file_type();
+                self.ready = items.into_iter().collect();
+                self.pending = next_gen;
+            }
+        }
+    }
+}
 
-                if !ft.is_dir() {
-                    return Err(RecDirIterTopIsNotDir);
-                }
+#[derive(Debug)]
+pub struct RecDirIterTopIsNotDir;
 
-                try_append_dir_elems(&mut iter.to_traverse, &iter.top, &null_path)
-                    .err()
-                    .map(annot_error)
-            }
-            Err(e) => Some(e),
-        };
+impl TryFrom<PathBuf> for RecDirIter {
+    type Error = RecDirIterTopIsNotDir;
 
-        Ok(iter)
+    fn try_from(top: PathBuf) -> Result<Self, Self::Error> {
+        Self::new(top, false)
     }
 }
 
@@ -129,22 +264,26 @@ impl Iterator for RecDirIter {
     type Item = io::Result<PathBuf>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.error.is_some() {
-            self.to_traverse.truncate(0);
-            return Some(Err(self.error.take().unwrap()));
-        }
+        loop {
+            if let Some(p) = self.ready.pop_front() {
+                return Some(Ok(p));
+            }
 
-        if let Some(p) = self.to_traverse.pop_front() {
-            self.append_dir_elems(&p);
-            Some(Ok(p))
-        } else {
-            None
+            if let Some(e) = self.error.take() {
+                return Some(Err(e));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            self.advance();
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         if self.error.is_none() {
-            (self.to_traverse.len(), None)
+            (self.ready.len(), None)
         } else {
             (0, Some(0))
         }
@@ -220,4 +359,113 @@ mod tests {
 
         assert!(r.is_err());
     }
+
+    /// Sets up `<base>/dir/sub` plus a `<base>/dir/sub/loop -> <base>/dir` symlink, to exercise
+    /// `follow_symlinks`' cycle detection.
+    fn make_symlink_cycle(base: &Path) -> PathBuf {
+        let dir = base.join("dir");
+        let sub = dir.join("sub");
+
+        std::fs::create_dir_all(&sub).unwrap();
+        std::os::unix::fs::symlink(&dir, sub.join("loop")).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn follow_symlinks_off_does_not_descend_into_symlinked_dirs() {
+        let base = std::env::temp_dir().join("durduff-rec-dir-iter-test-no-follow");
+        let _ = std::fs::remove_dir_all(&base);
+
+        let dir = make_symlink_cycle(&base);
+
+        let paths: Vec<_> = RecDirIter::try_from(dir)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(paths, vec![PathBuf::from("sub"), PathBuf::from("sub/loop")]);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn follow_symlinks_on_detects_cycle_without_looping() {
+        let base = std::env::temp_dir().join("durduff-rec-dir-iter-test-follow");
+        let _ = std::fs::remove_dir_all(&base);
+
+        let dir = make_symlink_cycle(&base);
+
+        let paths: Vec<_> = RecDirIter::new(dir, true)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        // "sub/loop" is yielded (it's still a real directory entry) but never descended into,
+        // so it never reappears as "sub/loop/sub", "sub/loop/sub/loop", and so on.
+        assert_eq!(paths, vec![PathBuf::from("sub"), PathBuf::from("sub/loop")]);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    /// Builds a moderately wide/deep tree under `base` so the parallel and sequential scans have
+    /// more than one directory per generation to race against each other.
+    fn make_wide_tree(base: &Path) {
+        for top in ["a", "b", "c", "d"] {
+            for sub in ["x", "y"] {
+                std::fs::create_dir_all(base.join(top).join(sub)).unwrap();
+                std::fs::write(base.join(top).join(sub).join("f"), b"data").unwrap();
+            }
+
+            std::fs::write(base.join(top).join("leaf"), b"data").unwrap();
+        }
+    }
+
+    #[test]
+    fn with_threads_matches_sequential_output() {
+        let base = std::env::temp_dir().join("durduff-rec-dir-iter-test-threads");
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(&base).unwrap();
+
+        make_wide_tree(&base);
+
+        let sequential: Vec<_> = RecDirIter::new(base.clone(), false)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        let parallel: Vec<_> = RecDirIter::new(base.clone(), false)
+            .unwrap()
+            .with_threads(4)
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(parallel, sequential);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn with_threads_one_is_a_no_op() {
+        let base = std::env::temp_dir().join("durduff-rec-dir-iter-test-threads-one");
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(&base).unwrap();
+
+        make_wide_tree(&base);
+
+        let paths: Vec<_> = RecDirIter::new(base.clone(), false)
+            .unwrap()
+            .with_threads(1)
+            .map(Result::unwrap)
+            .collect();
+
+        let expected: Vec<_> = RecDirIter::new(base.clone(), false)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(paths, expected);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
 }
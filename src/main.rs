@@ -1,44 +1,74 @@
 pub mod cli;
+pub mod config;
+pub mod hasher;
 pub mod io;
 pub mod iter;
+pub mod manifest;
 #[macro_use]
 pub mod osvec;
+pub mod pathspec;
+pub mod platform;
+pub mod rename_detect;
+pub mod tree_source;
 pub mod verdict;
 pub mod verdictor;
 
-use std::convert::TryFrom;
+use std::cell::RefCell;
 
 use std::ffi::OsString;
 
+use std::io::BufWriter;
 use std::io::Write;
 
 use std::os::unix::io::AsRawFd;
 
 use std::path::Path;
+use std::path::PathBuf;
 
 use crate::cli::parse_cli;
-use crate::cli::TtyEnabledOutput;
+use crate::cli::OutputFormat;
 
 use crate::io::fmt_error_kind;
 use crate::io::print_diff;
+use crate::io::resolve_color_codes;
+use crate::io::resolve_palette;
+use crate::io::use_progressive_printer;
 use crate::io::utf8_percent_encode_path;
-use crate::io::LineStatusColorCodes;
 use crate::io::PlainRecordPrinter;
 use crate::io::ProgressiveRecordPrinter;
 
 use crate::iter::cmp_paths;
 use crate::iter::OkIter;
+use crate::iter::ParVerdictIter;
 use crate::iter::RecDirIter;
 use crate::iter::SumIter;
 
+use crate::hasher::Sha256Hasher;
+
+use crate::manifest::generate_manifest;
+use crate::manifest::load_manifest;
+
+use crate::pathspec::read_exclude_from;
+use crate::pathspec::PathFilter;
+
+use crate::rename_detect::detect_renames;
+
+use crate::tree_source::AnyTreeSource;
+use crate::tree_source::FsTreeSource;
+use crate::tree_source::TarTreeSource;
+use crate::tree_source::TreeSource;
+
 use crate::verdict::Verdict;
 
 use crate::verdictor::Verdictor;
 
-// Provides the `print_build_info` function.
+// Provides the `print_build_info` function and the `GIT_COMMIT_SHORT`/`GIT_DIRTY`/
+// `BUILD_TIMESTAMP` constants.
 include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
 
-/// Returns `durduff` version from `Cargo.toml`.
+/// Returns `durduff` version from `Cargo.toml`, plus the build metadata `build.rs` captured: the
+/// short commit hash (suffixed `-dirty` if the working tree had uncommitted changes at build
+/// time) and the UTC build timestamp, e.g. `1.2.3 (abc1234-dirty, built 2024-01-02T03:04:05Z)`.
 pub fn get_version() -> String {
     let core = format!(
         "{}.{}.{}",
@@ -47,10 +77,17 @@ pub fn get_version() -> String {
         env!("CARGO_PKG_VERSION_PATCH"),
     );
 
-    match option_env!("CARGO_PKG_VERSION_PRE") {
+    let core = match option_env!("CARGO_PKG_VERSION_PRE") {
         Some(pre) => format!("{}-{}", core, pre),
         None => core,
-    }
+    };
+
+    let dirty_marker = if GIT_DIRTY { "-dirty" } else { "" };
+
+    format!(
+        "{} ({}{}, built {})",
+        core, GIT_COMMIT_SHORT, dirty_marker, BUILD_TIMESTAMP
+    )
 }
 
 #[derive(Clone, Copy, Eq, PartialEq)]
@@ -89,6 +126,67 @@ fn is_tty<S: AsRawFd>(stream: &S) -> bool {
     unsafe { libc::isatty(stream.as_raw_fd()) == 1 }
 }
 
+/// Checks whether `path` is a directory according to `source`, for a `PathFilter`'s dir-only
+/// patterns. Treats a missing/unreadable entry as "not a directory" rather than propagating the
+/// error: the entry's own verdict computation reports the real I/O failure.
+fn entry_is_dir<T: TreeSource>(source: &T, path: &Path) -> bool {
+    source.entry_info(path).map(|i| i.is_dir).unwrap_or(false)
+}
+
+/// Whether `path` should be read as a tar archive rather than a directory: either `forced` is set
+/// (`--old-tar`/`--new-tar`), or `path` has a `.tar` extension.
+fn is_tar_path(path: &Path, forced: bool) -> bool {
+    forced || path.extension().map_or(false, |ext| ext == "tar")
+}
+
+/// Builds the traversal iterator (for `SumIter`) and `TreeSource` for one side (`old`/`new`) of
+/// the comparison, treating `path` as a tar archive when `is_tar_path` says so, and as a live
+/// directory otherwise.
+fn open_tree_side<E: Write>(
+    path: &Path,
+    is_tar: bool,
+    dereference: bool,
+    jobs: usize,
+    side_name: &str,
+    bin_name: &str,
+    stderr: &mut E,
+) -> Result<(Box<dyn Iterator<Item = std::io::Result<PathBuf>>>, AnyTreeSource), i32> {
+    if is_tar {
+        match TarTreeSource::open(path) {
+            Ok((paths, source)) => Ok((Box::new(paths), AnyTreeSource::Tar(source))),
+            Err(e) => {
+                writeln!(
+                    stderr,
+                    "{}: could not read tar archive {}: {}",
+                    bin_name,
+                    utf8_percent_encode_path(path),
+                    e
+                )
+                .unwrap();
+                Err(ExecResult::Fatal.exit_code())
+            }
+        }
+    } else {
+        match RecDirIter::new(path.to_path_buf(), dereference) {
+            Ok(iter) => Ok((
+                Box::new(iter.with_threads(jobs)),
+                AnyTreeSource::Fs(FsTreeSource::new(path.to_path_buf())),
+            )),
+            Err(_) => {
+                writeln!(
+                    stderr,
+                    "{}: <{}> is not a directory: {}",
+                    bin_name,
+                    side_name,
+                    utf8_percent_encode_path(path)
+                )
+                .unwrap();
+                Err(ExecResult::Fatal.exit_code())
+            }
+        }
+    }
+}
+
 fn main() {
     let exit_code = {
         let raw_args: Vec<OsString> = std::env::args_os().collect();
@@ -108,16 +206,40 @@ fn main() {
     std::process::exit(exit_code);
 }
 
-/// Estimates the total number of files to process, when comparing directories `lhs` and `rhs`.
+/// Re-walks one side (`lhs`/`rhs`) of the comparison, for `calc_total`'s progress-report estimate.
+fn calc_total_side(
+    path: &Path,
+    is_tar: bool,
+    dereference: bool,
+    jobs: usize,
+) -> Box<dyn Iterator<Item = PathBuf>> {
+    if is_tar {
+        match TarTreeSource::open(path) {
+            Ok((iter, _)) => Box::new(iter.filter_map(Result::ok)),
+            Err(_) => Box::new(std::iter::empty()),
+        }
+    } else {
+        match RecDirIter::new(path.to_path_buf(), dereference) {
+            Ok(iter) => Box::new(iter.with_threads(jobs).filter_map(Result::ok)),
+            Err(_) => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+/// Estimates the total number of files to process, when comparing `lhs` and `rhs` (each either a
+/// directory or, when its matching `*_is_tar` is set, a tar archive).
 ///
 /// Useful for progress reporting.
-fn calc_total(lhs: &Path, rhs: &Path) -> usize {
-    let lhs_iter = RecDirIter::try_from(lhs.to_path_buf())
-        .unwrap()
-        .filter_map(Result::ok);
-    let rhs_iter = RecDirIter::try_from(rhs.to_path_buf())
-        .unwrap()
-        .filter_map(Result::ok);
+fn calc_total(
+    lhs: &Path,
+    lhs_is_tar: bool,
+    rhs: &Path,
+    rhs_is_tar: bool,
+    dereference: bool,
+    jobs: usize,
+) -> usize {
+    let lhs_iter = calc_total_side(lhs, lhs_is_tar, dereference, jobs);
+    let rhs_iter = calc_total_side(rhs, rhs_is_tar, dereference, jobs);
 
     SumIter::new(lhs_iter, rhs_iter, cmp_paths).count()
 }
@@ -151,57 +273,148 @@ where
         }
     };
 
-    let lhs_dir_iter = match RecDirIter::try_from(args.old_dir.clone()) {
-        Ok(i) => i,
-        Err(_) => {
-            writeln!(
-                &mut stderr,
-                "{}: <old> is not a directory: {}",
-                cli.bin_name,
-                utf8_percent_encode_path(&args.old_dir)
-            )
-            .unwrap();
-            return ExecResult::Fatal.exit_code();
-        }
-    };
+    if let Some(out_path) = &args.generate_manifest {
+        let file = match std::fs::File::create(out_path) {
+            Ok(f) => f,
+            Err(e) => {
+                writeln!(
+                    &mut stderr,
+                    "{}: could not create {}: {}",
+                    cli.bin_name,
+                    utf8_percent_encode_path(out_path),
+                    e
+                )
+                .unwrap();
+                return ExecResult::Fatal.exit_code();
+            }
+        };
 
-    let rhs_dir_iter = match RecDirIter::try_from(args.new_dir.clone()) {
-        Ok(i) => i,
-        Err(_) => {
-            writeln!(
-                &mut stderr,
-                "{}: <new> is not a directory: {}",
-                cli.bin_name,
-                utf8_percent_encode_path(&args.new_dir)
-            )
-            .unwrap();
-            return ExecResult::Fatal.exit_code();
-        }
+        let mut writer = BufWriter::new(file);
+
+        return match generate_manifest(&args.old_dir, &mut writer, args.nul_terminated) {
+            Ok(()) => ExecResult::NonFatal(ErrorStatus::NoErrors, DiffStatus::TreesSame).exit_code(),
+            Err(e) => {
+                writeln!(&mut stderr, "{}: could not generate manifest: {}", cli.bin_name, e).unwrap();
+                ExecResult::Fatal.exit_code()
+            }
+        };
+    }
+
+    let jobs = args.jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+
+    let (rhs_box_iter, rhs_source) = match open_tree_side(
+        &args.new_dir,
+        is_tar_path(&args.new_dir, args.new_tar),
+        args.dereference,
+        jobs,
+        "new",
+        &cli.bin_name,
+        &mut stderr,
+    ) {
+        Ok(v) => v,
+        Err(code) => return code,
     };
 
-    let mut lhs_io_err = None;
+    let mut lhs_io_err: Option<std::io::Error> = None;
     let mut rhs_io_err = None;
 
-    let lhs_ok_iter = OkIter::new(lhs_dir_iter, &mut lhs_io_err);
-    let rhs_ok_iter = OkIter::new(rhs_dir_iter, &mut rhs_io_err);
+    let rhs_ok_iter = OkIter::new(rhs_box_iter, &mut rhs_io_err);
+
+    // In `--old-manifest` mode, `<old>` is never read as a directory: `lhs_io_err` simply stays
+    // `None`, since `load_manifest` reports its own failures eagerly, below, rather than through a
+    // streaming-traversal error like `RecDirIter`'s/`TarTreeSource`'s.
+    let (sum_dir_items, manifest_tree_source, lhs_source): (
+        Vec<_>,
+        Option<_>,
+        Option<AnyTreeSource>,
+    ) = if let Some(manifest_path) = &args.old_manifest {
+        let (manifest_iter, manifest_tree_source) = match load_manifest(manifest_path) {
+            Ok(m) => m,
+            Err(e) => {
+                writeln!(
+                    &mut stderr,
+                    "{}: could not read manifest {}: {}",
+                    cli.bin_name,
+                    utf8_percent_encode_path(manifest_path),
+                    e
+                )
+                .unwrap();
+                return ExecResult::Fatal.exit_code();
+            }
+        };
+
+        let lhs_iter: Box<dyn Iterator<Item = PathBuf> + '_> =
+            Box::new(manifest_iter.filter_map(Result::ok));
+        let rhs_iter: Box<dyn Iterator<Item = PathBuf> + '_> = Box::new(rhs_ok_iter);
+
+        let items = SumIter::new(lhs_iter, rhs_iter, cmp_paths).collect();
+
+        (items, Some(manifest_tree_source), None)
+    } else {
+        let (lhs_box_iter, lhs_source) = match open_tree_side(
+            &args.old_dir,
+            is_tar_path(&args.old_dir, args.old_tar),
+            args.dereference,
+            jobs,
+            "old",
+            &cli.bin_name,
+            &mut stderr,
+        ) {
+            Ok(v) => v,
+            Err(code) => return code,
+        };
+
+        let lhs_ok_iter = OkIter::new(lhs_box_iter, &mut lhs_io_err);
+
+        // `ParVerdictIter` dispatches items to worker threads, so they (and the closure
+        // computing their verdicts) need to be `Send + 'static`; collecting severs the borrow
+        // `OkIter` holds on `lhs_io_err`/`rhs_io_err`.
+        let items = SumIter::new(lhs_ok_iter, rhs_ok_iter, cmp_paths).collect();
+
+        (items, None, Some(lhs_source))
+    };
+
+    let sum_dir_items_count = sum_dir_items.len();
 
-    let sum_dir_iter = SumIter::new(lhs_ok_iter, rhs_ok_iter, cmp_paths);
+    // A manifest comparison always hashes: the manifest side only ever has a digest on hand, never
+    // raw bytes to read in lockstep.
+    let hasher_factory = if manifest_tree_source.is_some() || args.hash {
+        Some(Sha256Hasher::new as fn() -> _)
+    } else {
+        None
+    };
 
-    let mut verdictor = Verdictor::new(&args.old_dir, &args.new_dir, args.block_size);
+    // Dedicated `TreeSource` handles for `--detect-renames`'s content hashing, so it doesn't need
+    // to borrow `verdictor` (which gets moved into the per-thread comparison closure below).
+    // `rename_lhs_source` falls back to an unused, empty-path `FsTreeSource` whenever
+    // `--old-manifest` is set, since that's mutually exclusive with `--detect-renames`.
+    let rename_lhs_source = lhs_source
+        .clone()
+        .unwrap_or_else(|| AnyTreeSource::Fs(FsTreeSource::new(PathBuf::new())));
+    let rename_rhs_source = rhs_source.clone();
 
     let mut error_status = ErrorStatus::NoErrors;
     let mut diff_status = DiffStatus::TreesSame;
 
     let check_verdict = |(v, _): &(Verdict, _)| match v {
         Verdict::Error(_) => error_status = ErrorStatus::SomeErrors,
-        Verdict::Same => (),
+        Verdict::Same | Verdict::Ignored => (),
         _ => diff_status = DiffStatus::TreesDiff,
     };
 
+    // Under `--brief`, text-format output doesn't need every entry: once `diff_status` (set by
+    // `check_verdict`, above) flips to `TreesDiff`, the trees are already known to differ, so the
+    // rest of the comparison can be skipped. JSON/NDJSON output can't take this shortcut: its own
+    // summary object (see `print_diff`) needs a full, accurate tally, so it always sees every
+    // verdict; `print_diff` handles JSON's `--brief` by suppressing per-entry records instead.
     let keep_printing = |(v, _): &(Verdict, _)| {
-        if args.brief {
+        if args.brief && args.format == OutputFormat::Text {
             match v {
-                Verdict::Error(_) | Verdict::Same => true,
+                Verdict::Error(_) | Verdict::Same | Verdict::Ignored => true,
                 _ => false,
             }
         } else {
@@ -209,36 +422,160 @@ where
         }
     };
 
-    let verdicts = sum_dir_iter
-        .map(|v| verdictor.get_verdict(v))
-        .inspect(check_verdict)
-        .take_while(keep_printing);
+    let exclude_matcher = match &args.config {
+        Some(config_path) => {
+            match config::parse_config_file(config_path, &mut std::collections::HashSet::new()) {
+                Ok(config) => config::ExcludeMatcher::from_config(&config),
+                Err(e) => {
+                    writeln!(
+                        &mut stderr,
+                        "{}: could not read config {}: {}",
+                        cli.bin_name,
+                        utf8_percent_encode_path(config_path),
+                        e
+                    )
+                    .unwrap();
+                    return ExecResult::Fatal.exit_code();
+                }
+            }
+        }
+        None => config::ExcludeMatcher::empty(),
+    };
 
-    let progressive = match args.progress {
-        TtyEnabledOutput::Never => false,
-        TtyEnabledOutput::Auto => stderr_is_tty,
-        TtyEnabledOutput::Always => true,
+    let exclude_from_patterns = match &args.exclude_from {
+        Some(exclude_from_path) => match read_exclude_from(exclude_from_path) {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                writeln!(
+                    &mut stderr,
+                    "{}: could not read {}: {}",
+                    cli.bin_name,
+                    utf8_percent_encode_path(exclude_from_path),
+                    e
+                )
+                .unwrap();
+                return ExecResult::Fatal.exit_code();
+            }
+        },
+        None => Vec::new(),
     };
 
-    let color_codes = match (args.color, stdout_is_tty) {
-        (TtyEnabledOutput::Never, _) | (TtyEnabledOutput::Auto, false) => {
-            LineStatusColorCodes::no_color()
+    let path_filter = PathFilter::new(&exclude_from_patterns, &args.exclude, &args.include);
+
+    let palette = match resolve_palette(args.palette.as_deref()) {
+        Ok(palette) => palette,
+        Err(e) => {
+            writeln!(&mut stderr, "{}: invalid palette: {}", cli.bin_name, e).unwrap();
+            return ExecResult::Fatal.exit_code();
         }
-        (TtyEnabledOutput::Always, _) | (TtyEnabledOutput::Auto, true) => {
-            LineStatusColorCodes::color()
+    };
+
+    let par_iter = if let Some(manifest_tree_source) = manifest_tree_source {
+        let verdictor = RefCell::new(Verdictor::new(
+            manifest_tree_source,
+            rhs_source.clone(),
+            args.block_size,
+            hasher_factory,
+        ));
+
+        let new_source = rhs_source;
+
+        ParVerdictIter::new(
+            sum_dir_items.into_iter(),
+            move |(sel, path)| {
+                let is_excluded = exclude_matcher.is_excluded(&path)
+                    || path_filter.is_excluded(&path, || entry_is_dir(&new_source, &path));
+
+                if is_excluded {
+                    (Verdict::Ignored, path)
+                } else {
+                    verdictor.borrow_mut().get_verdict((sel, path))
+                }
+            },
+            jobs,
+        )
+    } else {
+        let lhs_source = lhs_source.unwrap();
+
+        let verdictor = RefCell::new(Verdictor::new(
+            lhs_source.clone(),
+            rhs_source.clone(),
+            args.block_size,
+            hasher_factory,
+        ));
+
+        let old_source = lhs_source;
+        let new_source = rhs_source;
+
+        ParVerdictIter::new(
+            sum_dir_items.into_iter(),
+            move |(sel, path)| {
+                let is_excluded = exclude_matcher.is_excluded(&path)
+                    || path_filter.is_excluded(&path, || {
+                        entry_is_dir(&old_source, &path) || entry_is_dir(&new_source, &path)
+                    });
+
+                if is_excluded {
+                    (Verdict::Ignored, path)
+                } else {
+                    verdictor.borrow_mut().get_verdict((sel, path))
+                }
+            },
+            jobs,
+        )
+    };
+
+    let verdicts = par_iter.inspect(check_verdict).take_while(keep_printing);
+
+    let verdicts: Box<dyn Iterator<Item = (Verdict, PathBuf)>> = if args.detect_renames {
+        let collected: Vec<_> = verdicts.collect();
+
+        let (detected, rename_error_status) =
+            detect_renames(collected, &rename_lhs_source, &rename_rhs_source);
+
+        if rename_error_status == ErrorStatus::SomeErrors {
+            error_status = ErrorStatus::SomeErrors;
         }
+
+        Box::new(detected.into_iter())
+    } else {
+        Box::new(verdicts)
     };
 
+    let progressive = use_progressive_printer(args.progress, stderr_is_tty);
+    let color_codes = resolve_color_codes(args.color, stdout_is_tty, &palette);
+
     if progressive {
         writeln!(&mut stderr, "calculating totals... ").unwrap();
-        let total_hint = calc_total(&args.old_dir, &args.new_dir);
+        // In `--old-manifest` mode the manifest is already fully loaded, so the count gathered
+        // while building `sum_dir_items` is the total; there's no second directory to re-walk.
+        let total_hint = if args.old_manifest.is_some() {
+            sum_dir_items_count
+        } else {
+            calc_total(
+                &args.old_dir,
+                is_tar_path(&args.old_dir, args.old_tar),
+                &args.new_dir,
+                is_tar_path(&args.new_dir, args.new_tar),
+                args.dereference,
+                jobs,
+            )
+        };
         writeln!(&mut stderr, "done.\n").unwrap();
 
         print_diff(
             verdicts,
-            ProgressiveRecordPrinter::new(&mut stdout, &mut stderr, total_hint),
+            ProgressiveRecordPrinter::new(
+                &mut stdout,
+                &mut stderr,
+                total_hint,
+                color_codes.progress.clone(),
+                color_codes.reset.clone(),
+            ),
             color_codes.clone(),
             args.nul_terminated,
+            args.format,
+            args.brief,
         )
     } else {
         print_diff(
@@ -246,6 +583,8 @@ where
             PlainRecordPrinter::new(&mut stdout, &mut stderr),
             color_codes.clone(),
             args.nul_terminated,
+            args.format,
+            args.brief,
         )
     }
 
@@ -254,7 +593,7 @@ where
     }
 
     let exec_result = if let Some(e) = lhs_io_err.or(rhs_io_err) {
-        stderr.write_all(color_codes.error).unwrap();
+        stderr.write_all(&color_codes.error).unwrap();
         let error_desc = fmt_error_kind(e.kind());
         writeln!(
             &mut stderr,
@@ -262,13 +601,13 @@ where
             cli.bin_name, error_desc, e
         )
         .unwrap();
-        stderr.write_all(color_codes.reset).unwrap();
+        stderr.write_all(&color_codes.reset).unwrap();
         ExecResult::Fatal
     } else {
         if error_status == ErrorStatus::SomeErrors {
-            stderr.write_all(color_codes.error).unwrap();
+            stderr.write_all(&color_codes.error).unwrap();
             writeln!(&mut stderr, "{}: nonfatal errors encountered", cli.bin_name).unwrap();
-            stderr.write_all(color_codes.reset).unwrap();
+            stderr.write_all(&color_codes.reset).unwrap();
         }
 
         ExecResult::NonFatal(error_status, diff_status)
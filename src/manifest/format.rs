@@ -0,0 +1,181 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::platform::bytes_to_os_string;
+use crate::platform::os_str_to_bytes;
+
+/// A single recorded entry: the size and content digest a regular file had when the manifest was
+/// generated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ManifestEntry {
+    pub len: u64,
+    pub hash: Vec<u8>,
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Decodes a single lowercase-hex digit, returning `None` on anything else.
+fn hex_nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        _ => None,
+    }
+}
+
+/// Lowercase-hex-encodes `bytes`.
+pub(super) fn hex_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+
+    for b in bytes {
+        out.push(HEX_DIGITS[(b >> 4) as usize] as char);
+        out.push(HEX_DIGITS[(b & 0xf) as usize] as char);
+    }
+
+    out
+}
+
+/// Decodes a lowercase-hex byte string produced by `hex_encode`, returning `None` on malformed
+/// input.
+pub(super) fn hex_decode(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+
+    bytes
+        .chunks(2)
+        .map(|pair| Some((hex_nibble(pair[0])? << 4) | hex_nibble(pair[1])?))
+        .collect()
+}
+
+/// Percent-encodes `\n` (which would otherwise split a newline-separated manifest record in two)
+/// and `%` (so decoding stays unambiguous) in `bytes`, leaving everything else — including
+/// non-UTF-8 bytes — untouched. Applied regardless of `--null`, so a record round-trips
+/// byte-for-byte either way.
+fn percent_encode_path_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+
+    for &b in bytes {
+        if b == b'\n' || b == b'%' {
+            out.push(b'%');
+            out.push(HEX_DIGITS[(b >> 4) as usize]);
+            out.push(HEX_DIGITS[(b & 0xf) as usize]);
+        } else {
+            out.push(b);
+        }
+    }
+
+    out
+}
+
+/// Reverses `percent_encode_path_bytes`, returning `None` on a malformed (truncated or non-hex)
+/// escape.
+fn percent_decode_path_bytes(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter().copied();
+
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hi = hex_nibble(iter.next()?)?;
+            let lo = hex_nibble(iter.next()?)?;
+            out.push((hi << 4) | lo);
+        } else {
+            out.push(b);
+        }
+    }
+
+    Some(out)
+}
+
+/// Formats one manifest record as `<sha256-hex> <len> <percent-encoded path>`, without a trailing
+/// terminator (the caller appends `\n` or `\0` depending on `--null`).
+///
+/// Operates on raw bytes (rather than `str`) so that non-Unicode paths survive the round trip,
+/// the same reasoning `print_diff`'s raw/NUL output follows; the path itself is percent-encoded
+/// (see `percent_encode_path_bytes`) so a path containing an embedded `\n` can't fracture a
+/// newline-separated manifest into an unparseable record.
+pub(super) fn format_record(path: &Path, entry: &ManifestEntry) -> Vec<u8> {
+    let mut line = format!("{} {} ", hex_encode(&entry.hash), entry.len).into_bytes();
+    line.extend_from_slice(&percent_encode_path_bytes(&os_str_to_bytes(path.as_os_str())));
+    line
+}
+
+/// Parses one manifest record produced by `format_record`.
+pub(super) fn parse_record(record: &[u8]) -> Option<(PathBuf, ManifestEntry)> {
+    let mut parts = record.splitn(3, |&b| b == b' ');
+
+    let hash = hex_decode(parts.next()?)?;
+    let len = std::str::from_utf8(parts.next()?).ok()?.parse::<u64>().ok()?;
+    let path = percent_decode_path_bytes(parts.next()?)?;
+
+    let path = PathBuf::from(bytes_to_os_string(path));
+
+    Some((path, ManifestEntry { len, hash }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes = vec![0x00, 0x0f, 0xa5, 0xff];
+
+        assert_eq!(hex_decode(hex_encode(&bytes).as_bytes()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let entry = ManifestEntry {
+            len: 1234,
+            hash: vec![0xde, 0xad, 0xbe, 0xef],
+        };
+
+        let record = format_record(Path::new("foo/bar baz"), &entry);
+
+        let (path, parsed) = parse_record(&record).unwrap();
+
+        assert_eq!(path, Path::new("foo/bar baz"));
+        assert_eq!(parsed, entry);
+    }
+
+    #[test]
+    fn malformed_record_is_rejected() {
+        assert!(parse_record(b"not-hex 5 foo").is_none());
+        assert!(parse_record(b"ab").is_none());
+        assert!(parse_record(b"deadbeef notanumber foo").is_none());
+    }
+
+    #[test]
+    fn path_with_embedded_newline_round_trips_as_one_record() {
+        let entry = ManifestEntry {
+            len: 42,
+            hash: vec![0xca, 0xfe],
+        };
+
+        let record = format_record(Path::new("foo\nbar"), &entry);
+
+        // the record must not itself contain a literal '\n', or it would split into two lines in
+        // a newline-separated manifest.
+        assert!(!record.contains(&b'\n'));
+
+        let (path, parsed) = parse_record(&record).unwrap();
+
+        assert_eq!(path, Path::new("foo\nbar"));
+        assert_eq!(parsed, entry);
+    }
+
+    #[test]
+    fn path_with_percent_round_trips() {
+        let entry = ManifestEntry {
+            len: 7,
+            hash: vec![0x01],
+        };
+
+        let record = format_record(Path::new("100%done"), &entry);
+        let (path, parsed) = parse_record(&record).unwrap();
+
+        assert_eq!(path, Path::new("100%done"));
+        assert_eq!(parsed, entry);
+    }
+}
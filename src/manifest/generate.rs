@@ -0,0 +1,57 @@
+use std::convert::TryFrom;
+
+use std::fs::File;
+
+use std::io;
+use std::io::Write;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::hasher::digest_reader;
+use crate::hasher::Sha256Hasher;
+
+use crate::iter::RecDirIter;
+
+use super::format::format_record;
+use super::format::ManifestEntry;
+
+/// Block size used when hashing files while generating a manifest.
+const GENERATE_BLKSIZE: usize = 512 << 10; // 512 KiB
+
+/// Walks `root` with `RecDirIter` (the same traversal a live comparison would use) and writes one
+/// record per regular file to `out`, sorted in `cmp_paths` order. Directories and symlinks are
+/// skipped: a manifest only ever describes the regular files `ManifestTreeSource` can stand in
+/// for.
+///
+/// Records are separated by NUL bytes when `nul_terminated` is set, or newlines otherwise, so a
+/// generated manifest is loaded back with the same convention `--null` already uses elsewhere.
+pub fn generate_manifest<W: Write>(root: &Path, out: &mut W, nul_terminated: bool) -> io::Result<()> {
+    let separator: &[u8] = if nul_terminated { b"\0" } else { b"\n" };
+
+    let dir_iter = RecDirIter::try_from(root.to_path_buf())
+        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "not a directory"))?;
+
+    for path in dir_iter {
+        let path: PathBuf = path?;
+
+        let full_path = root.join(&path);
+        let metadata = full_path.symlink_metadata()?;
+
+        if !metadata.file_type().is_file() {
+            continue;
+        }
+
+        let hash = digest_reader(File::open(&full_path)?, GENERATE_BLKSIZE, Sha256Hasher::new())?;
+
+        let entry = ManifestEntry {
+            len: metadata.len(),
+            hash,
+        };
+
+        out.write_all(&format_record(&path, &entry))?;
+        out.write_all(separator)?;
+    }
+
+    Ok(())
+}
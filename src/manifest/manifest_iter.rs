@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use std::fs;
+
+use std::io;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use std::sync::Arc;
+
+use crate::iter::cmp_paths;
+
+use crate::tree_source::ManifestTreeSource;
+
+use super::format::parse_record;
+
+/// Iterates the paths recorded in a manifest, in `cmp_paths` order, the same shape `RecDirIter`
+/// yields so it plugs straight into `SumIter`.
+pub struct ManifestIter {
+    paths: std::vec::IntoIter<PathBuf>,
+}
+
+impl Iterator for ManifestIter {
+    type Item = io::Result<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.paths.next().map(Ok)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.paths.size_hint()
+    }
+}
+
+/// Reads the manifest at `path`, splitting records on whichever of `\n`/`\0` the file uses (a
+/// manifest generated with `--null` is NUL-separated; otherwise newline-separated, matching
+/// `generate_manifest`).
+///
+/// Returns an iterator over the manifest's paths (for `SumIter`) paired with a `ManifestTreeSource`
+/// `Verdictor` can compare against.
+pub fn load_manifest(path: &Path) -> io::Result<(ManifestIter, ManifestTreeSource)> {
+    let contents = fs::read(path)?;
+
+    let separator: u8 = if contents.contains(&b'\0') { b'\0' } else { b'\n' };
+
+    let mut paths = Vec::new();
+    let mut entries = HashMap::new();
+
+    for record in contents.split(|&b| b == separator) {
+        if record.is_empty() {
+            continue;
+        }
+
+        let (path, entry) = parse_record(record).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "malformed manifest record")
+        })?;
+
+        paths.push(path.clone());
+        entries.insert(path, entry);
+    }
+
+    paths.sort_by(cmp_paths);
+
+    let iter = ManifestIter {
+        paths: paths.into_iter(),
+    };
+
+    Ok((iter, ManifestTreeSource::new(Arc::new(entries))))
+}
@@ -0,0 +1,8 @@
+mod format;
+mod generate;
+mod manifest_iter;
+
+pub use self::format::ManifestEntry;
+pub use self::generate::generate_manifest;
+pub use self::manifest_iter::load_manifest;
+pub use self::manifest_iter::ManifestIter;
@@ -0,0 +1,269 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use regex::Regex;
+
+/// One compiled rule in a `PathFilter`'s ordered list.
+struct Rule {
+    regex: Regex,
+
+    /// `true` for a rule that re-includes a path an earlier rule excluded (`--include`, or a
+    /// `!`-prefixed line read from `--exclude-from`).
+    negate: bool,
+
+    /// `true` for a pattern with a trailing `/`: matches directories only.
+    dir_only: bool,
+}
+
+/// Matches paths against an ordered list of gitignore/pathspec-style glob rules, built from
+/// `--exclude-from`, `--exclude` and `--include`.
+///
+/// Patterns are evaluated, in that order, against every ancestor directory of a path and the path
+/// itself (so a pattern matching a directory also covers everything underneath it, the way
+/// `.gitignore` rules do); the *last* pattern to match at a given level decides that level's
+/// verdict, which then carries down to its descendants unless a deeper level's patterns override
+/// it. The path is included by default.
+///
+/// - a pattern with no `/` matches any path component, at any depth;
+/// - a leading `/` anchors the pattern to the root of the comparison;
+/// - `*` matches within one path component, `**` spans `/`, `?` matches one non-`/` char;
+/// - a trailing `/` restricts the pattern to directories;
+/// - a `!`-prefixed pattern re-includes a path an earlier pattern excluded.
+#[derive(Clone)]
+pub struct PathFilter {
+    rules: Vec<Rule>,
+}
+
+impl PathFilter {
+    /// Filter that excludes nothing.
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Builds a filter from, in evaluation order: the patterns read from `--exclude-from` (if
+    /// any), then `--exclude` patterns, then `--include` patterns (each of which re-includes
+    /// whatever an earlier rule excluded, unless it has a leading `!`, which makes it exclude).
+    pub fn new(exclude_from_patterns: &[String], exclude: &[String], include: &[String]) -> Self {
+        let mut rules =
+            Vec::with_capacity(exclude_from_patterns.len() + exclude.len() + include.len());
+
+        rules.extend(exclude_from_patterns.iter().map(|p| compile(p, false)));
+        rules.extend(exclude.iter().map(|p| compile(p, false)));
+        rules.extend(include.iter().map(|p| compile(p, true)));
+
+        Self { rules }
+    }
+
+    /// Returns whether `relative_path` is excluded by this filter. `is_dir` is called at most
+    /// once (for `relative_path` itself; its ancestors are always directories) and only if a
+    /// dir-only pattern needs it, so a filter with none never stats the filesystem.
+    pub fn is_excluded(&self, relative_path: &Path, is_dir: impl Fn() -> bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+
+        let has_dir_only = self.rules.iter().any(|r| r.dir_only);
+
+        let mut excluded = false;
+        let mut prefix = PathBuf::new();
+        let mut components = relative_path.components().peekable();
+
+        while let Some(component) = components.next() {
+            prefix.push(component);
+
+            let is_last = components.peek().is_none();
+            let prefix_is_dir = if is_last && has_dir_only { is_dir() } else { true };
+
+            let prefix_str = prefix.to_string_lossy();
+
+            for rule in &self.rules {
+                if rule.dir_only && !prefix_is_dir {
+                    continue;
+                }
+
+                if rule.regex.is_match(&prefix_str) {
+                    excluded = !rule.negate;
+                }
+            }
+        }
+
+        excluded
+    }
+}
+
+/// Reads `--exclude-from`-style patterns from `path`: one pattern per line, blank lines and
+/// `#`-prefixed comment lines ignored.
+pub fn read_exclude_from(path: &Path) -> io::Result<Vec<String>> {
+    let contents = fs::read_to_string(path)?;
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Compiles one gitignore-style glob `pattern` into a `Rule`. `force_include` is `true` for
+/// `--include` patterns, which re-include by default; a leading `!` flips that back to excluding,
+/// mirroring gitignore's own double-negation rule.
+fn compile(pattern: &str, force_include: bool) -> Rule {
+    let (leading_negate, pattern) = match pattern.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+
+    let negate = force_include ^ leading_negate;
+
+    let anchored_prefix = pattern.starts_with('/');
+    let pattern = if anchored_prefix { &pattern[1..] } else { pattern };
+
+    let dir_only = !pattern.is_empty() && pattern.ends_with('/');
+    let pattern = if dir_only {
+        &pattern[..pattern.len() - 1]
+    } else {
+        pattern
+    };
+
+    let anchored = anchored_prefix || pattern.contains('/');
+
+    let body = translate_glob(pattern);
+
+    let full = if anchored {
+        format!("^{}$", body)
+    } else {
+        format!("^(?:.*/)?{}$", body)
+    };
+
+    Rule {
+        regex: Regex::new(&full).unwrap(),
+        negate,
+        dir_only,
+    }
+}
+
+/// Translates a gitignore-style glob body (no anchoring slashes, no leading `!`) into an
+/// unanchored regex fragment: `*` matches within a path component, `**` spans `/`, `?` matches one
+/// non-`/` char, everything else is escaped and matched literally.
+fn translate_glob(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                if chars.get(i + 2) == Some(&'/') {
+                    out.push_str("(?:.*/)?");
+                    i += 3;
+                } else {
+                    out.push_str(".*");
+                    i += 2;
+                }
+            }
+            '*' => {
+                out.push_str("[^/]*");
+                i += 1;
+            }
+            '?' => {
+                out.push_str("[^/]");
+                i += 1;
+            }
+            c @ ('.' | '+' | '(' | ')' | '|' | '^' | '$' | '[' | ']' | '{' | '}' | '\\') => {
+                out.push('\\');
+                out.push(c);
+                i += 1;
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(exclude: &[&str], include: &[&str]) -> PathFilter {
+        let exclude: Vec<String> = exclude.iter().map(|s| s.to_string()).collect();
+        let include: Vec<String> = include.iter().map(|s| s.to_string()).collect();
+
+        PathFilter::new(&[], &exclude, &include)
+    }
+
+    fn excludes(filter: &PathFilter, path: &str, is_dir: bool) -> bool {
+        filter.is_excluded(Path::new(path), || is_dir)
+    }
+
+    #[test]
+    fn empty_filter_excludes_nothing() {
+        let filter = PathFilter::empty();
+
+        assert!(!excludes(&filter, "anything", false));
+    }
+
+    #[test]
+    fn unanchored_pattern_matches_any_depth() {
+        let filter = matcher(&["*.o"], &[]);
+
+        assert!(excludes(&filter, "foo.o", false));
+        assert!(excludes(&filter, "src/foo.o", false));
+        assert!(!excludes(&filter, "foo.rs", false));
+    }
+
+    #[test]
+    fn anchored_pattern_matches_root_only() {
+        let filter = matcher(&["/target"], &[]);
+
+        assert!(excludes(&filter, "target", true));
+        assert!(!excludes(&filter, "src/target", true));
+    }
+
+    #[test]
+    fn excluded_directory_covers_its_descendants() {
+        let filter = matcher(&["target"], &[]);
+
+        assert!(excludes(&filter, "target", true));
+        assert!(excludes(&filter, "target/debug/build.rs", false));
+    }
+
+    #[test]
+    fn include_re_includes_under_excluded_directory() {
+        let filter = matcher(&["target"], &["target/keep.txt"]);
+
+        assert!(excludes(&filter, "target/debug/build.rs", false));
+        assert!(!excludes(&filter, "target/keep.txt", false));
+    }
+
+    #[test]
+    fn dir_only_pattern_does_not_match_files() {
+        let filter = matcher(&["build/"], &[]);
+
+        assert!(excludes(&filter, "build", true));
+        assert!(!excludes(&filter, "build", false));
+    }
+
+    #[test]
+    fn double_star_spans_separators() {
+        let filter = matcher(&["a/**/b"], &[]);
+
+        assert!(excludes(&filter, "a/b", false));
+        assert!(excludes(&filter, "a/x/y/b", false));
+        assert!(!excludes(&filter, "a/b/c", false));
+    }
+
+    #[test]
+    fn later_pattern_overrides_earlier_one() {
+        let filter = matcher(&["*.txt"], &["keep.txt"]);
+
+        assert!(excludes(&filter, "drop.txt", false));
+        assert!(!excludes(&filter, "keep.txt", false));
+    }
+}
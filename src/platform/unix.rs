@@ -0,0 +1,18 @@
+use std::ffi::OsStr;
+use std::ffi::OsString;
+
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::ffi::OsStringExt;
+
+/// Returns the raw bytes making up `s`'s underlying representation.
+///
+/// On Unix, `OsStr` already is an arbitrary byte sequence (mod interior NULs), so this is just a
+/// reinterpretation, not a re-encoding.
+pub fn os_str_to_bytes(s: &OsStr) -> Vec<u8> {
+    s.as_bytes().to_vec()
+}
+
+/// Inverse of `os_str_to_bytes`.
+pub fn bytes_to_os_string(bytes: Vec<u8>) -> OsString {
+    OsString::from_vec(bytes)
+}
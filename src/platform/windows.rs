@@ -0,0 +1,125 @@
+use std::ffi::OsStr;
+use std::ffi::OsString;
+
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::ffi::OsStringExt;
+
+/// Returns `s`'s underlying UTF-16 representation re-encoded as WTF-8, so the "raw"
+/// NUL-terminated output mode round-trips non-Unicode (lone-surrogate) names the same way it
+/// already does on Unix.
+pub fn os_str_to_bytes(s: &OsStr) -> Vec<u8> {
+    wtf8_encode(s.encode_wide())
+}
+
+/// Inverse of `os_str_to_bytes`.
+pub fn bytes_to_os_string(bytes: Vec<u8>) -> OsString {
+    OsString::from_wide(&wtf8_decode(&bytes))
+}
+
+/// Encodes a sequence of UTF-16 code units (as yielded by `OsStrExt::encode_wide`, lone
+/// surrogates included) as WTF-8.
+fn wtf8_encode(units: impl Iterator<Item = u16>) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut units = units.peekable();
+
+    while let Some(high) = units.next() {
+        if (0xD800..=0xDBFF).contains(&high) {
+            if let Some(&low) = units.peek() {
+                if (0xDC00..=0xDFFF).contains(&low) {
+                    units.next();
+                    let c = 0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(low) - 0xDC00);
+                    push_utf8_scalar(&mut out, c);
+                    continue;
+                }
+            }
+        }
+
+        match char::from_u32(u32::from(high)) {
+            Some(c) => push_utf8_scalar(&mut out, c as u32),
+            // unpaired surrogate: WTF-8 encodes it as if it were its own 3-byte scalar
+            None => push_surrogate(&mut out, high),
+        }
+    }
+
+    out
+}
+
+fn push_utf8_scalar(out: &mut Vec<u8>, c: u32) {
+    let c = char::from_u32(c).expect("valid scalar value");
+    let mut buf = [0_u8; 4];
+    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+fn push_surrogate(out: &mut Vec<u8>, surrogate: u16) {
+    let c = u32::from(surrogate);
+    out.push(0xE0 | (c >> 12) as u8);
+    out.push(0x80 | ((c >> 6) & 0x3F) as u8);
+    out.push(0x80 | (c & 0x3F) as u8);
+}
+
+/// Decodes a WTF-8 byte sequence produced by `wtf8_encode` back into UTF-16 code units.
+fn wtf8_decode(bytes: &[u8]) -> Vec<u16> {
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b0 = bytes[i];
+
+        if b0 < 0x80 {
+            out.push(u16::from(b0));
+            i += 1;
+        } else if b0 & 0xE0 == 0xC0 && i + 1 < bytes.len() {
+            let c = (u32::from(b0 & 0x1F) << 6) | u32::from(bytes[i + 1] & 0x3F);
+            out.push(c as u16);
+            i += 2;
+        } else if b0 & 0xF0 == 0xE0 && i + 2 < bytes.len() {
+            let c = (u32::from(b0 & 0x0F) << 12)
+                | (u32::from(bytes[i + 1] & 0x3F) << 6)
+                | u32::from(bytes[i + 2] & 0x3F);
+            out.push(c as u16);
+            i += 3;
+        } else if b0 & 0xF8 == 0xF0 && i + 3 < bytes.len() {
+            let c = (u32::from(b0 & 0x07) << 18)
+                | (u32::from(bytes[i + 1] & 0x3F) << 12)
+                | (u32::from(bytes[i + 2] & 0x3F) << 6)
+                | u32::from(bytes[i + 3] & 0x3F);
+            let c = c - 0x10000;
+            out.push(0xD800 + (c >> 10) as u16);
+            out.push(0xDC00 + (c & 0x3FF) as u16);
+            i += 4;
+        } else {
+            // malformed byte: skip it rather than failing, since this is only ever fed bytes
+            // `wtf8_encode` itself produced
+            i += 1;
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_round_trips() {
+        let units: Vec<u16> = "hello".encode_utf16().collect();
+        let bytes = wtf8_encode(units.iter().copied());
+        assert_eq!(bytes, b"hello");
+        assert_eq!(wtf8_decode(&bytes), units);
+    }
+
+    #[test]
+    fn astral_plane_round_trips() {
+        let units: Vec<u16> = "\u{1F980}".encode_utf16().collect();
+        let bytes = wtf8_encode(units.iter().copied());
+        assert_eq!(wtf8_decode(&bytes), units);
+    }
+
+    #[test]
+    fn lone_surrogate_round_trips() {
+        let units = vec![0xD800_u16];
+        let bytes = wtf8_encode(units.iter().copied());
+        assert_eq!(wtf8_decode(&bytes), units);
+    }
+}
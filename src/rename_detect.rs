@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use std::io;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::hasher::digest_reader;
+use crate::hasher::Sha256Hasher;
+use crate::tree_source::TreeSource;
+use crate::verdict::Verdict;
+use crate::ErrorStatus;
+
+/// Block size used when hashing file contents for rename detection. Kept separate from
+/// `Verdictor`'s block size, since this hash is computed unconditionally (regardless of
+/// `--hash`/`--block-size`) whenever `--detect-renames` is on.
+const HASH_BLKSIZE: usize = 512 << 10; // 512 KiB
+
+/// Reduces the file at `path` (read through `source`) to a SHA-256 digest.
+fn hash_contents<T: TreeSource>(source: &T, path: &Path) -> io::Result<Vec<u8>> {
+    digest_reader(source.open(path)?, HASH_BLKSIZE, Sha256Hasher::new())
+}
+
+/// Pairs `Deleted`/`Added` entries in `verdicts` that have identical content, re-emitting each
+/// matched pair as a single `Renamed` entry in place of the `Deleted` one (the `Added` entry is
+/// dropped). Entries sharing a content hash are paired by sorted path order, deterministically;
+/// any leftovers (when one side has more matches than the other) are left as plain add/delete.
+///
+/// Hashing failures leave the affected entry untouched and report `ErrorStatus::SomeErrors`.
+pub fn detect_renames<L: TreeSource, R: TreeSource>(
+    verdicts: Vec<(Verdict, PathBuf)>,
+    lhs: &L,
+    rhs: &R,
+) -> (Vec<(Verdict, PathBuf)>, ErrorStatus) {
+    let mut error_status = ErrorStatus::NoErrors;
+
+    let mut deleted_by_hash: HashMap<Vec<u8>, Vec<(usize, PathBuf)>> = HashMap::new();
+    let mut added_by_hash: HashMap<Vec<u8>, Vec<(usize, PathBuf)>> = HashMap::new();
+
+    for (index, (verdict, path)) in verdicts.iter().enumerate() {
+        match verdict {
+            Verdict::Deleted => match hash_contents(lhs, path) {
+                Ok(hash) => deleted_by_hash
+                    .entry(hash)
+                    .or_insert_with(Vec::new)
+                    .push((index, path.clone())),
+                Err(_) => error_status = ErrorStatus::SomeErrors,
+            },
+            Verdict::Added => match hash_contents(rhs, path) {
+                Ok(hash) => added_by_hash
+                    .entry(hash)
+                    .or_insert_with(Vec::new)
+                    .push((index, path.clone())),
+                Err(_) => error_status = ErrorStatus::SomeErrors,
+            },
+            _ => (),
+        }
+    }
+
+    let mut renamed_to: HashMap<usize, PathBuf> = HashMap::new();
+    let mut folded_adds: HashSet<usize> = HashSet::new();
+
+    for (hash, mut deleted_group) in deleted_by_hash {
+        let mut added_group = match added_by_hash.remove(&hash) {
+            Some(g) => g,
+            None => continue,
+        };
+
+        deleted_group.sort_by(|a, b| a.1.cmp(&b.1));
+        added_group.sort_by(|a, b| a.1.cmp(&b.1));
+
+        for ((deleted_index, _), (added_index, new_path)) in
+            deleted_group.into_iter().zip(added_group.into_iter())
+        {
+            renamed_to.insert(deleted_index, new_path);
+            folded_adds.insert(added_index);
+        }
+    }
+
+    let verdicts = verdicts
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (verdict, path))| {
+            if folded_adds.contains(&index) {
+                None
+            } else if let Some(new_path) = renamed_to.remove(&index) {
+                Some((Verdict::Renamed(path), new_path))
+            } else {
+                Some((verdict, path))
+            }
+        })
+        .collect();
+
+    (verdicts, error_status)
+}
@@ -0,0 +1,63 @@
+use std::fs::File;
+
+use std::io;
+use std::io::Read;
+use std::io::Take;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::EntryInfo;
+use super::FsTreeSource;
+use super::TarTreeSource;
+use super::TreeSource;
+
+/// Reader returned by `AnyTreeSource::open`, matching whichever concrete source produced it.
+pub enum AnyReader {
+    Fs(File),
+    Tar(Take<File>),
+}
+
+impl Read for AnyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            AnyReader::Fs(r) => r.read(buf),
+            AnyReader::Tar(r) => r.read(buf),
+        }
+    }
+}
+
+/// `TreeSource` that's either a live directory or a tar archive, the choice made once at startup
+/// (by `--old-tar`/`--new-tar` or a `.tar` extension). Letting `old`/`new` each independently be
+/// either kind through one `TreeSource` impl keeps `Verdictor`'s `lhs`/`rhs` type parameters from
+/// needing a variant per old/new combination.
+#[derive(Clone)]
+pub enum AnyTreeSource {
+    Fs(FsTreeSource),
+    Tar(TarTreeSource),
+}
+
+impl TreeSource for AnyTreeSource {
+    type Reader = AnyReader;
+
+    fn entry_info(&self, path: &Path) -> io::Result<EntryInfo> {
+        match self {
+            AnyTreeSource::Fs(s) => s.entry_info(path),
+            AnyTreeSource::Tar(s) => s.entry_info(path),
+        }
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self {
+            AnyTreeSource::Fs(s) => s.read_link(path),
+            AnyTreeSource::Tar(s) => s.read_link(path),
+        }
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Self::Reader> {
+        match self {
+            AnyTreeSource::Fs(s) => s.open(path).map(AnyReader::Fs),
+            AnyTreeSource::Tar(s) => s.open(path).map(AnyReader::Tar),
+        }
+    }
+}
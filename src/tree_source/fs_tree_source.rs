@@ -0,0 +1,47 @@
+use std::fs::File;
+
+use std::io;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::EntryInfo;
+use super::TreeSource;
+
+/// `TreeSource` backed by a directory on the local filesystem.
+///
+/// This is what `Verdictor` used directly (via `std::fs`) before `TreeSource` existed.
+#[derive(Clone)]
+pub struct FsTreeSource {
+    base: PathBuf,
+}
+
+impl FsTreeSource {
+    pub fn new(base: PathBuf) -> Self {
+        Self { base }
+    }
+}
+
+impl TreeSource for FsTreeSource {
+    type Reader = File;
+
+    fn entry_info(&self, path: &Path) -> io::Result<EntryInfo> {
+        let metadata = self.base.join(path).symlink_metadata()?;
+        let file_type = metadata.file_type();
+
+        Ok(EntryInfo {
+            is_dir: file_type.is_dir(),
+            is_file: file_type.is_file(),
+            is_symlink: file_type.is_symlink(),
+            len: metadata.len(),
+        })
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.base.join(path).read_link()
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Self::Reader> {
+        File::open(self.base.join(path))
+    }
+}
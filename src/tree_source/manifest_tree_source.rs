@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use std::io;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use std::sync::Arc;
+
+use crate::manifest::ManifestEntry;
+
+use super::EntryInfo;
+use super::TreeSource;
+
+/// `TreeSource` backed by a loaded manifest, so a live directory can be compared against a
+/// snapshot taken earlier without keeping a full copy of the old tree around.
+///
+/// A manifest only ever records regular files (see `generate_manifest`), and never their bytes,
+/// only their size and digest, so `open`/`read_link` are never expected to be called; `Verdictor`
+/// reaches `entry_info` and `precomputed_digest` instead.
+#[derive(Clone)]
+pub struct ManifestTreeSource {
+    entries: Arc<HashMap<PathBuf, ManifestEntry>>,
+}
+
+impl ManifestTreeSource {
+    pub(crate) fn new(entries: Arc<HashMap<PathBuf, ManifestEntry>>) -> Self {
+        Self { entries }
+    }
+
+    fn entry(&self, path: &Path) -> io::Result<&ManifestEntry> {
+        self.entries
+            .get(path)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+impl TreeSource for ManifestTreeSource {
+    type Reader = io::Empty;
+
+    fn entry_info(&self, path: &Path) -> io::Result<EntryInfo> {
+        let entry = self.entry(path)?;
+
+        Ok(EntryInfo {
+            is_dir: false,
+            is_file: true,
+            is_symlink: false,
+            len: entry.len,
+        })
+    }
+
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(io::Error::from(io::ErrorKind::InvalidData))
+    }
+
+    fn open(&self, _path: &Path) -> io::Result<Self::Reader> {
+        Err(io::Error::from(io::ErrorKind::InvalidData))
+    }
+
+    fn precomputed_digest(&self, path: &Path) -> io::Result<Option<Vec<u8>>> {
+        Ok(Some(self.entry(path)?.hash.clone()))
+    }
+}
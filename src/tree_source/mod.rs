@@ -0,0 +1,51 @@
+mod any_tree_source;
+mod fs_tree_source;
+mod manifest_tree_source;
+mod tar_tree_source;
+
+pub use self::any_tree_source::*;
+pub use self::fs_tree_source::*;
+pub use self::manifest_tree_source::*;
+pub use self::tar_tree_source::*;
+
+use std::io;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Type and size information about an entry, abstracting over what `Verdictor` needs from
+/// `std::fs::Metadata`, a `tar::Header`, or any other tree-shaped source of files.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct EntryInfo {
+    pub is_dir: bool,
+    pub is_file: bool,
+    pub is_symlink: bool,
+    pub len: u64,
+}
+
+/// Abstracts file-tree access so `Verdictor` can compare a live directory, a tar archive, or any
+/// other tree-shaped source of files, without reaching for `std::fs` directly.
+///
+/// Paths passed to these methods are relative to whatever root the implementation was constructed
+/// with (e.g. the comparison's `old_dir`/`new_dir`, or an archive's internal root).
+pub trait TreeSource {
+    type Reader: io::Read;
+
+    /// Returns type/size info about `path`, without following symlinks.
+    fn entry_info(&self, path: &Path) -> io::Result<EntryInfo>;
+
+    /// Returns the target of the symlink at `path`.
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Opens `path` for reading its contents.
+    fn open(&self, path: &Path) -> io::Result<Self::Reader>;
+
+    /// Returns a content digest for `path` already known to this source, if it has one, so
+    /// `Verdictor`'s hash-based comparison mode can use it directly instead of streaming the file
+    /// through a fresh hasher. Sources backed by actual bytes (`FsTreeSource`, `TarTreeSource`)
+    /// have no such shortcut and keep the default of `None`; `ManifestTreeSource` is the only
+    /// source implemented so far that overrides it, since a manifest only ever stores the digest.
+    fn precomputed_digest(&self, _path: &Path) -> io::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
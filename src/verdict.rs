@@ -1,11 +1,29 @@
 use std::io::ErrorKind;
 
+use std::path::PathBuf;
+
 /// Verdict (whether a file is changed)
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Verdict {
     Same,
     Deleted,
     Added,
-    Modified,
+
+    /// The file's contents (or, for a symlink, its target) differ. Holds the offset of the first
+    /// differing byte, when the comparison mode found one: the exact byte-for-byte comparison
+    /// does, but the hash-based mode (`--hash`) never has both sides' bytes in hand at the same
+    /// time, so it stays `None`, as do symlink and file-length mismatches, neither of which walk
+    /// the contents.
+    Modified(Option<u64>),
+
+    /// Both sides exist at this path, but as different entry types (e.g. a file replaced by a
+    /// directory).
+    TypeChanged,
+
+    /// The file was moved or renamed; holds the path it used to have. The path paired with this
+    /// verdict (e.g. in `(Verdict, PathBuf)`) is its new path.
+    Renamed(PathBuf),
+
+    Ignored,
     Error(ErrorKind),
 }
@@ -1,13 +1,13 @@
-use std::fs::File;
-
 use std::io;
 use std::io::Read;
 
 use std::path::Path;
 use std::path::PathBuf;
 
+use crate::hasher::Hasher;
 use crate::io::ReadIntMitigator;
 use crate::iter::SumIterSelector;
+use crate::tree_source::TreeSource;
 use crate::verdict::Verdict;
 
 /// Default block size used to read files
@@ -15,135 +15,216 @@ const DEFAULT_BLKSIZE: usize = 512 << 10; // 512 KiB
 
 /// Based on items from `SumIter`, gives verdicts whether files at a specified path (with different
 /// prefixes) differ.
-pub struct Verdictor<'a> {
-    lhs_prefix: &'a Path,
-    rhs_prefix: &'a Path,
+///
+/// File-tree access is abstracted behind `TreeSource`, so `lhs`/`rhs` may be live directories, tar
+/// archives, or any other tree-shaped source of files.
+///
+/// `Clone` lets `ParVerdictIter` hand each worker thread its own independent `Verdictor`.
+#[derive(Clone)]
+pub struct Verdictor<L: TreeSource, R: TreeSource> {
+    lhs: L,
+    rhs: R,
 
     blksize: usize,
+
+    /// When set, file contents are compared by reducing each side to a digest independently
+    /// (producing the digest via this factory) instead of reading both sides in lockstep. `None`
+    /// keeps the default exact byte-for-byte comparison.
+    hasher_factory: Option<fn() -> Box<dyn Hasher>>,
 }
 
-/// Couples I/O error with path prefix indicating which tree (lhs vs rhs) the error was encountered
-/// in.
+/// Indicates which tree (lhs vs rhs) an I/O error was encountered in.
 ///
-/// The prefix is usually stripped, because it's not printed in the normal output. However, it may
-/// be useful when reporting errors.
-type PrivError<'a> = (io::ErrorKind, &'a Path);
+/// Used instead of a path prefix, since a `TreeSource` (e.g. a tar archive) may not have one.
+#[derive(Clone, Copy)]
+enum Side {
+    Lhs,
+    Rhs,
+}
+
+/// Couples I/O error with the side (lhs vs rhs) it was encountered on.
+type PrivError = (io::ErrorKind, Side);
 
 /// Result based on PrivError
-type PrivResult<'a> = Result<Verdict, PrivError<'a>>;
+type PrivResult = Result<Verdict, PrivError>;
 
 /// Converts `(result, path)` into `(Verdict, PathBuf)` suitable for printing.
 fn priv_result_to_ver_path(result: PrivResult, path: PathBuf) -> (Verdict, PathBuf) {
     match result {
         Ok(verdict) => (verdict, path),
-        Err((error_kind, prefix)) => (Verdict::Error(error_kind), prefix.join(path)),
+        Err((error_kind, _side)) => (Verdict::Error(error_kind), path),
     }
 }
 
-/// Used to convert `std::io::Result` into `PrivResult`, annotating errors with the path to the
-/// directory tree where they were encountered.
+/// Used to convert `std::io::Result` into `PrivResult`, annotating errors with the tree (lhs vs
+/// rhs) they were encountered in.
 trait IoResult<T> {
-    fn annotate(self, path: &Path) -> Result<T, PrivError>;
+    fn annotate(self, side: Side) -> Result<T, PrivError>;
 }
 
 impl<T> IoResult<T> for io::Result<T> {
-    fn annotate(self, path: &Path) -> Result<T, PrivError> {
-        match self {
-            Ok(v) => Ok(v),
-            Err(e) => Err((e.kind(), path)),
-        }
+    fn annotate(self, side: Side) -> Result<T, PrivError> {
+        self.map_err(|e| (e.kind(), side))
     }
 }
 
-impl<'a> Verdictor<'a> {
-    /// Creates a new verdictor.
+impl<L: TreeSource, R: TreeSource> Verdictor<L, R> {
+    /// Creates a new verdictor. `hasher_factory` selects the hash-based content comparison mode
+    /// (see `cmp_contents_hashed`); `None` keeps the default exact comparison.
     pub fn new(
-        lhs_prefix: &'a Path,
-        rhs_prefix: &'a Path,
+        lhs: L,
+        rhs: R,
         blksize_override: Option<usize>,
-    ) -> Verdictor<'a> {
+        hasher_factory: Option<fn() -> Box<dyn Hasher>>,
+    ) -> Self {
         Verdictor {
-            lhs_prefix,
-            rhs_prefix,
+            lhs,
+            rhs,
 
             blksize: blksize_override.unwrap_or(DEFAULT_BLKSIZE),
+
+            hasher_factory,
         }
     }
 
     /// Compares symlink target paths.
-    fn cmp_symlinks(&self, lhs: &Path, rhs: &Path) -> PrivResult<'a> {
-        let ll = lhs.read_link().annotate(self.lhs_prefix)?;
-        let rl = rhs.read_link().annotate(self.rhs_prefix)?;
+    fn cmp_symlinks(&self, suffix: &Path) -> PrivResult {
+        let ll = self.lhs.read_link(suffix).annotate(Side::Lhs)?;
+        let rl = self.rhs.read_link(suffix).annotate(Side::Rhs)?;
 
         if ll == rl {
             Ok(Verdict::Same)
         } else {
-            Ok(Verdict::Modified)
+            Ok(Verdict::Modified(None))
         }
     }
 
-    /// Compares the contents of files `lhs` and `rhs`.
-    fn cmp_contents(&mut self, lhs: &Path, rhs: &Path) -> PrivResult<'a> {
-        let lhs_file = File::open(&lhs).annotate(self.lhs_prefix)?;
-        let rhs_file = File::open(&rhs).annotate(self.rhs_prefix)?;
+    /// Compares the contents of the files at `suffix` on both sides.
+    fn cmp_contents(&mut self, suffix: &Path) -> PrivResult {
+        match self.hasher_factory {
+            Some(factory) => self.cmp_contents_hashed(suffix, factory),
+            None => self.cmp_contents_exact(suffix),
+        }
+    }
 
-        let mut miti_lhs = ReadIntMitigator(lhs_file);
-        let mut miti_rhs = ReadIntMitigator(rhs_file);
+    /// Reads both files in lockstep, comparing them block by block. Short-circuits as soon as a
+    /// differing block (or read length) is found, reporting the offset of the first differing
+    /// byte (or, when the files' lengths merely differ without either side's content yet
+    /// disagreeing, the offset where the shorter one ran out).
+    fn cmp_contents_exact(&mut self, suffix: &Path) -> PrivResult {
+        let lhs_reader = self.lhs.open(suffix).annotate(Side::Lhs)?;
+        let rhs_reader = self.rhs.open(suffix).annotate(Side::Rhs)?;
+
+        let mut miti_lhs = ReadIntMitigator(lhs_reader);
+        let mut miti_rhs = ReadIntMitigator(rhs_reader);
 
         let mut lhs_buf = vec![0_u8; self.blksize];
         let mut rhs_buf = vec![0_u8; self.blksize];
 
+        let mut bytes_read: u64 = 0;
+
         loop {
-            let lhs_bytes_no = miti_lhs.read(&mut lhs_buf).annotate(self.lhs_prefix)?;
-            let rhs_bytes_no = miti_rhs.read(&mut rhs_buf).annotate(self.rhs_prefix)?;
+            let lhs_bytes_no = miti_lhs.read(&mut lhs_buf).annotate(Side::Lhs)?;
+            let rhs_bytes_no = miti_rhs.read(&mut rhs_buf).annotate(Side::Rhs)?;
 
             if lhs_bytes_no != rhs_bytes_no {
-                return Ok(Verdict::Modified);
+                let common = lhs_bytes_no.min(rhs_bytes_no);
+                let mismatch = lhs_buf[..common]
+                    .iter()
+                    .zip(rhs_buf[..common].iter())
+                    .position(|(l, r)| l != r)
+                    .unwrap_or(common);
+                return Ok(Verdict::Modified(Some(bytes_read + mismatch as u64)));
             } else if lhs_bytes_no == 0 {
                 return Ok(Verdict::Same);
-            } else if lhs_buf != rhs_buf {
-                return Ok(Verdict::Modified);
+            } else if lhs_buf[..lhs_bytes_no] != rhs_buf[..rhs_bytes_no] {
+                let mismatch = lhs_buf[..lhs_bytes_no]
+                    .iter()
+                    .zip(rhs_buf[..rhs_bytes_no].iter())
+                    .position(|(l, r)| l != r)
+                    .unwrap();
+                return Ok(Verdict::Modified(Some(bytes_read + mismatch as u64)));
+            }
+
+            bytes_read += lhs_bytes_no as u64;
+        }
+    }
+
+    /// Reduces each side to a streaming digest independently (so neither side needs to be
+    /// randomly readable at the same time as the other) and compares only the final digests.
+    fn cmp_contents_hashed(&mut self, suffix: &Path, factory: fn() -> Box<dyn Hasher>) -> PrivResult {
+        let lhs_digest = Self::side_digest(&self.lhs, suffix, Side::Lhs, self.blksize, factory)?;
+        let rhs_digest = Self::side_digest(&self.rhs, suffix, Side::Rhs, self.blksize, factory)?;
+
+        if lhs_digest == rhs_digest {
+            Ok(Verdict::Same)
+        } else {
+            Ok(Verdict::Modified(None))
+        }
+    }
+
+    /// Produces a digest for `suffix` on one side of the comparison, preferring a digest `source`
+    /// already has on hand (e.g. `ManifestTreeSource`, which only ever stores one) over streaming
+    /// the file's contents through a fresh hasher.
+    fn side_digest<T: TreeSource>(
+        source: &T,
+        suffix: &Path,
+        side: Side,
+        blksize: usize,
+        factory: fn() -> Box<dyn Hasher>,
+    ) -> Result<Vec<u8>, PrivError> {
+        if let Some(digest) = source.precomputed_digest(suffix).annotate(side)? {
+            return Ok(digest);
+        }
+
+        let reader = source.open(suffix).annotate(side)?;
+        Self::digest(reader, side, blksize, factory())
+    }
+
+    /// Streams `reader` through `hasher`, block by block, producing the final digest.
+    fn digest<Rd: Read>(
+        reader: Rd,
+        side: Side,
+        blksize: usize,
+        mut hasher: Box<dyn Hasher>,
+    ) -> Result<Vec<u8>, PrivError> {
+        let mut miti = ReadIntMitigator(reader);
+        let mut buf = vec![0_u8; blksize];
+
+        loop {
+            let bytes_no = miti.read(&mut buf).annotate(side)?;
+
+            if bytes_no == 0 {
+                return Ok(hasher.finish());
             }
+
+            hasher.write(&buf[..bytes_no]);
         }
     }
 
     /// Compares files with the paths ending with suffix `suffix` and beginning with prefixes
     /// passed to `new`.
-    fn cmp_files(&mut self, suffix: &Path) -> PrivResult<'a> {
-        let lhs_path = self.lhs_prefix.join(&suffix);
-        let rhs_path = self.rhs_prefix.join(&suffix);
-
-        let lhs_metadata = lhs_path.symlink_metadata().annotate(self.lhs_prefix)?;
-        let rhs_metadata = rhs_path.symlink_metadata().annotate(self.rhs_prefix)?;
-
-        let lhs_file_type = lhs_metadata.file_type();
-        let rhs_file_type = rhs_metadata.file_type();
-
-        let lhs_ftype = (
-            lhs_file_type.is_dir(),
-            lhs_file_type.is_file(),
-            lhs_file_type.is_symlink(),
-        );
-        let rhs_ftype = (
-            rhs_file_type.is_dir(),
-            rhs_file_type.is_file(),
-            rhs_file_type.is_symlink(),
-        );
+    fn cmp_files(&mut self, suffix: &Path) -> PrivResult {
+        let lhs_info = self.lhs.entry_info(suffix).annotate(Side::Lhs)?;
+        let rhs_info = self.rhs.entry_info(suffix).annotate(Side::Rhs)?;
+
+        let lhs_ftype = (lhs_info.is_dir, lhs_info.is_file, lhs_info.is_symlink);
+        let rhs_ftype = (rhs_info.is_dir, rhs_info.is_file, rhs_info.is_symlink);
 
         if lhs_ftype != rhs_ftype {
-            Ok(Verdict::Modified)
-        } else if lhs_file_type.is_symlink() {
-            self.cmp_symlinks(&lhs_path, &rhs_path)
-        } else if lhs_file_type.is_file() {
-            if lhs_metadata.len() == rhs_metadata.len() {
-                self.cmp_contents(&lhs_path, &rhs_path)
+            Ok(Verdict::TypeChanged)
+        } else if lhs_info.is_symlink {
+            self.cmp_symlinks(suffix)
+        } else if lhs_info.is_file {
+            if lhs_info.len == rhs_info.len {
+                self.cmp_contents(suffix)
             } else {
-                Ok(Verdict::Modified)
+                Ok(Verdict::Modified(None))
             }
-        } else if lhs_file_type.is_dir() {
+        } else if lhs_info.is_dir {
             Ok(Verdict::Same)
         } else {
-            Err((std::io::ErrorKind::InvalidData, self.lhs_prefix))
+            Err((std::io::ErrorKind::InvalidData, Side::Lhs))
         }
     }
 